@@ -31,19 +31,19 @@ impl Slice {
 
                         match token.attr().unwrap() {
                             Operator::Left => {
-                                while ops.back().map_or(false, |op| op.priority() <= priority) {
+                                while ops.back().is_some_and(|op| op.priority() <= priority) {
                                     result.push_back(Slice::Token(ops.pop_back().unwrap()));
                                 }
                                 ops.push_back(token);
                             }
                             Operator::Right => {
-                                while ops.back().map_or(false, |op| op.priority() < priority) {
+                                while ops.back().is_some_and(|op| op.priority() < priority) {
                                     result.push_back(Slice::Token(ops.pop_back().unwrap()));
                                 }
                                 ops.push_back(token);
                             }
                             Operator::Unary => {
-                                while ops.back().map_or(false, |op| op.priority() <= priority) {
+                                while ops.back().is_some_and(|op| op.priority() <= priority) {
                                     result.push_back(Slice::Token(ops.pop_back().unwrap()));
                                 }
                                 ops.push_back(token);
@@ -79,7 +79,11 @@ impl Slice {
         result
     }
 
-    fn cook_line_ops(slice: VecDeque<Slice>, line: &LineRef) -> Result<Stmt> {
+    fn cook_line_ops(
+        slice: VecDeque<Slice>,
+        line: &LineRef,
+        errors: &mut Vec<Error>,
+    ) -> Result<Stmt> {
         let slice = Self::make_suffix(line, slice);
         let mut nodes = VecDeque::new();
 
@@ -93,7 +97,7 @@ impl Slice {
                                 line.get(),
                             )
                         })?;
-                        nodes.push_back(token.to_stmt_unary_fn()(Box::new(operand)));
+                        nodes.push_back(token.to_unary_stmt(Box::new(operand)));
                     }
                     Some(_) => {
                         let right = nodes.pop_back().ok_or_else(|| {
@@ -118,7 +122,7 @@ impl Slice {
                 Slice::End(num) => {
                     line.set(num);
                 }
-                _ => nodes.push_back(slice.cook(line)?),
+                _ => nodes.push_back(slice.cook(line, errors)),
             }
         }
 
@@ -134,30 +138,45 @@ impl Slice {
         }
     }
 
-    fn cook(self, line: &LineRef) -> Result<Stmt> {
+    /// Cook the slice, putting errors in `errors` and going on with the next line.
+    /// A line with an error is left empty.
+    fn cook(self, line: &LineRef, errors: &mut Vec<Error>) -> Stmt {
         match self {
-            Slice::Token(token) => Ok(Stmt::Token(token, line.get())),
-            Slice::Line(slice) => Self::cook_line_ops(slice, line),
+            Slice::Token(token) => Stmt::Token(token, line.get()),
+            Slice::Line(slice) => Self::cook_line_ops(slice, line, errors).unwrap_or_else(|err| {
+                errors.push(err);
+                Stmt::Empty
+            }),
             Slice::Block(slice) => {
                 if slice.is_empty() {
-                    Ok(Stmt::Empty)
+                    Stmt::Empty
                 } else {
-                    Ok(Stmt::Block(
+                    Stmt::Block(
                         slice
                             .into_iter()
-                            .map(|slice| slice.cook(line))
-                            .collect::<Result<VecDeque<_>>>()?,
-                    ))
+                            .map(|slice| slice.cook(line, errors))
+                            .collect(),
+                    )
                 }
             }
-            Slice::End(num) => Err(Error::new("Unexpected end token".to_string(), num)),
+            Slice::End(num) => {
+                errors.push(Error::new("Unexpected end token".to_string(), num));
+                Stmt::Empty
+            }
         }
     }
 }
 
 impl Sliced {
+    /// Cook all lines, reporting the errors of all of them
     pub fn cook(self) -> Result<Cooked> {
         let line = Cell::new(1);
-        self.0.cook(&line).map(Cooked)
+        let mut errors = Vec::new();
+        let stmt = self.0.cook(&line, &mut errors);
+        if errors.is_empty() {
+            Ok(Cooked(stmt))
+        } else {
+            Err(Error::many(errors))
+        }
     }
 }
@@ -1,28 +1,605 @@
 use super::*;
+use std::iter::{Chain, Peekable};
+use std::str::Chars;
+use unicode_ident::{is_xid_continue, is_xid_start};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum Status {
     Normal,
-    Int,
-    Float,
-    // (is_escape, is_raw_string)
-    Str(bool, bool),
     Word,
     Comment,
 }
 
+/// Characters of the source, keeping track of the column of the next one
+#[derive(Clone)]
+struct Cursor<'s> {
+    chars: Peekable<Chain<Chars<'s>, Chars<'static>>>,
+    column: usize,
+}
+
+impl Iterator for Cursor<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+}
+
+impl Cursor<'_> {
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().copied()
+    }
+
+    /// Whether the next characters are the given ones, without reading them
+    fn sees(&self, expected: &str) -> bool {
+        let mut ahead = self.chars.clone();
+        expected.chars().all(|c| ahead.next() == Some(c))
+    }
+
+    /// The character `n` places ahead of the next one, without reading anything
+    fn peek_at(&self, n: usize) -> Option<char> {
+        self.chars.clone().nth(n)
+    }
+
+    /// Whether a number starts `n` places ahead, with a digit or a dot and a digit
+    fn starts_number(&self, n: usize) -> bool {
+        match self.peek_at(n) {
+            Some('0'..='9') => true,
+            Some('.') => matches!(self.peek_at(n + 1), Some('0'..='9')),
+            _ => false,
+        }
+    }
+
+    /// Whether a raw string opens here, after its `r`
+    fn sees_raw(&self) -> bool {
+        let mut ahead = self.chars.clone().skip_while(|&c| c == '#');
+        ahead.next() == Some('"')
+    }
+
+    /// Skip up to `count` spaces or tabs
+    fn skip_indent(&mut self, count: usize) {
+        for _ in 0..count {
+            match self.peek() {
+                Some(' ' | '\t') => self.next(),
+                _ => break,
+            };
+        }
+    }
+}
+
+/// Layout of a triple-quoted string, found by reading it ahead
+struct Layout {
+    /// Indentation common to its lines, removed from each of them
+    indent: usize,
+    /// Whether the text after the opening quotes is blank and skipped with its newline
+    skip_first: bool,
+    /// Whether the line before the closing quotes is blank and removed with its newline
+    trim_last: bool,
+}
+
+impl Layout {
+    fn of(mut chars: Cursor) -> Self {
+        let mut lines = vec![String::new()];
+        while let Some(c) = chars.next() {
+            match c {
+                '\n' => lines.push(String::new()),
+                '"' if chars.sees("\"\"") => break,
+                '\\' => {
+                    let line = lines.last_mut().unwrap();
+                    line.push(c);
+                    line.extend(chars.next());
+                }
+                _ => lines.last_mut().unwrap().push(c),
+            }
+        }
+
+        let is_blank = |line: &String| line.chars().all(|c| c == ' ' || c == '\t');
+        let skip_first = lines.len() > 1 && is_blank(&lines[0]);
+        let trim_last = lines.len() > 1 && is_blank(lines.last().unwrap());
+        let end = lines.len() - trim_last as usize;
+        let indent = lines[1.min(end)..end]
+            .iter()
+            .filter(|line| !is_blank(line))
+            .map(|line| line.chars().take_while(|&c| c == ' ' || c == '\t').count())
+            .min()
+            .unwrap_or(0);
+        Self {
+            indent,
+            skip_first,
+            trim_last,
+        }
+    }
+}
+
+/// Names start with `_` or a Unicode XID start character, like a letter
+fn is_name_start(c: char) -> bool {
+    c == '_' || is_xid_start(c)
+}
+
 impl<'s> Compilable<'s> {
+    /// Read the source of an interpolation up to its closing brace, the opening one already read.
+    /// Strings in it are written as they would be outside, or as text of the string around it:
+    /// `\"` then stands for `"`, and `\\` for `\` inside a string opened with `\"`.
+    fn interpolation(chars: &mut Cursor, line: &mut usize) -> Result<String> {
+        let start = *line;
+        let mut inner = String::new();
+        let mut depth = 0;
+        // Whether in a string of the interpolation, and if so whether it opened with `\"`
+        let mut quote = None;
+        let mut is_escape = false;
+        while let Some(mut c) = chars.next() {
+            let escaped = c == '\\'
+                && match chars.peek() {
+                    Some('"') => quote != Some(false),
+                    Some('\\') => quote == Some(true),
+                    _ => false,
+                };
+            if escaped {
+                c = chars.next().unwrap();
+            }
+            match c {
+                '\n' => *line += 1,
+                _ if is_escape => is_escape = false,
+                '\\' if quote.is_some() => is_escape = true,
+                '"' if quote.is_some() => quote = None,
+                '"' => quote = Some(escaped),
+                '{' if quote.is_none() => depth += 1,
+                '}' if quote.is_none() && depth == 0 => return Ok(inner),
+                '}' if quote.is_none() => depth -= 1,
+                _ => {}
+            }
+            inner.push(c);
+        }
+        Err(Error::new("Unclosed interpolation in string", start))
+    }
+
+    /// Read an escape sequence after its backslash at the given column:
+    /// `\n`, `\r`, `\t`, `\0`, `\\`, `\'`, `\"`, `\x7f` up to 7f, or `\u{...}`
+    fn escape(chars: &mut Cursor, line: usize, column: usize) -> Result<char> {
+        let c = chars
+            .next()
+            .ok_or_else(|| Error::at("Unfinished escape sequence", line, column))?;
+        match c {
+            'n' => Ok('\n'),
+            'r' => Ok('\r'),
+            't' => Ok('\t'),
+            '0' => Ok('\0'),
+            '\\' | '\'' | '"' => Ok(c),
+            'x' => {
+                let mut digits = String::new();
+                while let Some(c) = chars
+                    .peek()
+                    .filter(|c| c.is_ascii_hexdigit() && digits.len() < 2)
+                {
+                    chars.next();
+                    digits.push(c);
+                }
+                u8::from_str_radix(&digits, 16)
+                    .ok()
+                    .filter(|&n| digits.len() == 2 && n <= 0x7f)
+                    .map(char::from)
+                    .ok_or_else(|| {
+                        Error::at(
+                            format!(
+                                "Invalid escape sequence \\x{}, expected two hex digits up to 7f",
+                                digits
+                            ),
+                            line,
+                            column,
+                        )
+                    })
+            }
+            'u' => {
+                if chars.next() != Some('{') {
+                    return Err(Error::at(
+                        "Expected '{' after \\u in escape sequence",
+                        line,
+                        column,
+                    ));
+                }
+                let digits: String = chars
+                    .by_ref()
+                    .take_while(|&c| c != '}' && c != '\n')
+                    .collect();
+                u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .filter(|_| (1..=6).contains(&digits.len()))
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| {
+                        Error::at(
+                            format!("Invalid unicode escape sequence \\u{{{}}}", digits),
+                            line,
+                            column,
+                        )
+                    })
+            }
+            _ => Err(Error::at(
+                format!("Unknown escape sequence \\{}", c.escape_debug()),
+                line,
+                column,
+            )),
+        }
+    }
+
+    /// Read a character literal up to its closing quote, the opening one already read
+    fn char_literal(chars: &mut Cursor, line: usize) -> Result<char> {
+        let column = chars.column - 1;
+        let c = match chars.peek() {
+            Some('\'') => Err(Error::at("Empty character literal", line, column)),
+            Some('\n') | None => {
+                return Err(Error::at("Unclosed character literal", line, column));
+            }
+            Some('\\') => {
+                let column = chars.column;
+                chars.next();
+                Self::escape(chars, line, column)
+            }
+            Some(c) => {
+                chars.next();
+                Ok(c)
+            }
+        };
+        if chars.peek() == Some('\'') {
+            chars.next();
+            return c;
+        }
+
+        // Skip the rest of the literal so it does not open another one
+        let mut closed = false;
+        while let Some(c) = chars.peek().filter(|&c| c != '\n') {
+            chars.next();
+            if c == '\'' {
+                closed = true;
+                break;
+            }
+        }
+        c?;
+        Err(Error::at(
+            if closed {
+                "Character literal should hold a single character"
+            } else {
+                "Unclosed character literal"
+            },
+            line,
+            column,
+        ))
+    }
+
+    /// Read a string up to its closing quotes, the opening ones already read.
+    /// In interpolated strings like `f"..."`, `{expr}` interpolates an expression and
+    /// `{{` and `}}` stand for single braces. Braces in other strings are kept as they are.
+    /// Triple-quoted strings lose the indentation common to their lines,
+    /// and a blank first or last line next to the quotes.
+    /// Errors in escapes and interpolations are put in `errors` and reading goes on.
+    fn string(
+        chars: &mut Cursor,
+        line: &mut usize,
+        triple: bool,
+        interpolated: bool,
+        errors: &mut Vec<Error>,
+    ) -> Result<Token> {
+        let start = *line;
+        let layout = if triple {
+            Layout::of(chars.clone())
+        } else {
+            Layout {
+                indent: 0,
+                skip_first: false,
+                trim_last: false,
+            }
+        };
+        if layout.skip_first {
+            chars.find(|&c| c == '\n');
+            *line += 1;
+            chars.skip_indent(layout.indent);
+        }
+
+        let mut buffer = String::new();
+        let mut fragments = Vec::new();
+        // Length of the buffer before the last newline of the source
+        let mut last_line = 0;
+        loop {
+            let column = chars.column;
+            let Some(c) = chars.next() else {
+                return Err(Error::new("Unclosed string", start));
+            };
+            match c {
+                '\n' => {
+                    *line += 1;
+                    last_line = buffer.len();
+                    buffer.push(c);
+                    chars.skip_indent(layout.indent);
+                }
+                '"' if !triple => break,
+                '"' if chars.sees("\"\"") => {
+                    chars.nth(1);
+                    break;
+                }
+                '\\' => match Self::escape(chars, *line, column) {
+                    Ok(c) => buffer.push(c),
+                    Err(err) => errors.push(err),
+                },
+                '{' | '}' if interpolated && chars.peek() == Some(c) => {
+                    chars.next();
+                    buffer.push(c);
+                }
+                '{' if interpolated => {
+                    let (start, column) = (*line, chars.column);
+                    let inner = Self::interpolation(chars, line)?;
+                    match Compilable::at(&inner, start, column).compile() {
+                        Ok(stmt) => {
+                            fragments.push(Fragment::Str(std::mem::take(&mut buffer)));
+                            fragments.push(Fragment::Expr(stmt));
+                        }
+                        Err(err) => errors.push(err.with(format!(
+                            "When compiling interpolation {{{}}} at line {}",
+                            inner, start
+                        ))),
+                    }
+                }
+                '}' if interpolated => errors.push(Error::at(
+                    "Unmatched '}' in interpolated string, use '}}' for a literal brace",
+                    *line,
+                    column,
+                )),
+                _ => buffer.push(c),
+            }
+        }
+        if layout.trim_last {
+            buffer.truncate(last_line);
+        }
+
+        if fragments.is_empty() {
+            Ok(Token::Str(buffer))
+        } else {
+            fragments.push(Fragment::Str(buffer));
+            Ok(Token::Interp(Rc::new(fragments)))
+        }
+    }
+
+    /// Read a raw string like `r"..."` or `r#"..."#` after its `r`, taking every character as it is
+    fn raw_string(chars: &mut Cursor, line: &mut usize) -> Result<String> {
+        let start = *line;
+        let mut hashes = 0;
+        while chars.peek() == Some('#') {
+            chars.next();
+            hashes += 1;
+        }
+        if chars.next() != Some('"') {
+            return Err(Error::new("Expected '\"' to open raw string", start));
+        }
+
+        let close = "#".repeat(hashes);
+        let mut buffer = String::new();
+        loop {
+            match chars.next() {
+                Some('"') if chars.sees(&close) => {
+                    for _ in 0..hashes {
+                        chars.next();
+                    }
+                    return Ok(buffer);
+                }
+                Some(c) => {
+                    if c == '\n' {
+                        *line += 1;
+                    }
+                    buffer.push(c);
+                }
+                None => return Err(Error::new("Unclosed raw string", start)),
+            }
+        }
+    }
+
+    /// Read a number literal with its minus sign if any, like `-12`, `0xff`, `1_000u`,
+    /// `.5`, `1.5e-3` or `2f`. Numbers with a fraction or an exponent are floats,
+    /// others are ints unless a suffix `i`, `u`, `f` (or `n` for big integers) says otherwise.
+    fn number(chars: &mut Cursor, line: usize) -> Result<Token> {
+        let column = chars.column;
+        let mut text = String::new();
+        let mut digits = String::new();
+        let take = |chars: &mut Cursor, text: &mut String| -> Option<char> {
+            let c = chars.next()?;
+            text.push(c);
+            Some(c)
+        };
+
+        let negative = chars.peek() == Some('-');
+        if negative {
+            take(chars, &mut text);
+        }
+        let radix = match (chars.peek(), chars.peek_at(1)) {
+            (Some('0'), Some('x')) => 16,
+            (Some('0'), Some('o')) => 8,
+            (Some('0'), Some('b')) => 2,
+            _ => 10,
+        };
+        if radix != 10 {
+            take(chars, &mut text);
+            take(chars, &mut text);
+        }
+
+        let mut is_float = false;
+        loop {
+            match chars.peek() {
+                Some(c) if c.is_digit(radix) => digits.extend(take(chars, &mut text)),
+                Some('_') => {
+                    take(chars, &mut text);
+                }
+                // A fraction after a prefix is read to report it, rather than as another number
+                Some('.') if !is_float && chars.starts_number(1) => {
+                    is_float = true;
+                    digits.extend(take(chars, &mut text));
+                }
+                Some('e' | 'E') if radix == 10 => {
+                    let sign = matches!(chars.peek_at(1), Some('+' | '-')) as usize;
+                    if !matches!(chars.peek_at(1 + sign), Some('0'..='9')) {
+                        break;
+                    }
+                    is_float = true;
+                    for _ in 0..=sign {
+                        digits.extend(take(chars, &mut text));
+                    }
+                    while let Some(c) = chars.peek().filter(|c| c.is_ascii_digit() || *c == '_') {
+                        take(chars, &mut text);
+                        if c != '_' {
+                            digits.push(c);
+                        }
+                    }
+                    break;
+                }
+                _ => break,
+            }
+        }
+        let suffix = match chars.peek() {
+            Some('i' | 'u' | 'f' | 'n') => take(chars, &mut text),
+            _ => None,
+        };
+
+        let error = |msg: String| Error::at(msg, line, column);
+        if let Some(c) = chars.peek().filter(|&c| c.is_alphanumeric() || c == '_') {
+            return Err(error(format!(
+                "Unexpected character '{}' after number literal {}",
+                c, text
+            )));
+        }
+        if digits.is_empty() {
+            return Err(error(format!("Number literal {} has no digits", text)));
+        }
+
+        let sign = if negative { "-" } else { "" };
+        if is_float || suffix == Some('f') {
+            if let Some(suffix) = suffix.filter(|&suffix| suffix != 'f') {
+                return Err(error(format!(
+                    "Floating number literal {} cannot have suffix '{}'",
+                    text, suffix
+                )));
+            }
+            if radix != 10 {
+                return Err(error(format!(
+                    "Floating number literal {} cannot have a prefix",
+                    text
+                )));
+            }
+            let num: Float = format!("{}{}", sign, digits)
+                .parse()
+                .map_err(|_| error(format!("Invalid floating number literal {}", text)))?;
+            if num.is_infinite() {
+                return Err(error(format!(
+                    "Floating number literal {} is out of range",
+                    text
+                )));
+            }
+            return Ok(Token::Float(num));
+        }
+
+        match suffix {
+            #[cfg(feature = "bigint")]
+            Some('n') => {
+                let num = Big::parse_bytes(digits.as_bytes(), radix).unwrap();
+                Ok(Token::Big(if negative { -num } else { num }))
+            }
+            #[cfg(not(feature = "bigint"))]
+            Some('n') => Err(error(format!(
+                "Big integer literal {} needs the bigint feature",
+                text
+            ))),
+            Some('u') => {
+                let num = u64::from_str_radix(&digits, radix)
+                    .ok()
+                    .filter(|&num| !negative || num == 0)
+                    .ok_or_else(|| {
+                        error(format!(
+                            "Unsigned integer literal {} is out of range of uint",
+                            text
+                        ))
+                    })?;
+                Ok(Token::Uint(num))
+            }
+            _ => {
+                let num = i128::from_str_radix(&format!("{}{}", sign, digits), radix)
+                    .ok()
+                    .and_then(|num| Int::try_from(num).ok())
+                    .ok_or_else(|| {
+                        error(format!("Integer literal {} is out of range of int", text))
+                    })?;
+                Ok(Token::Int(num))
+            }
+        }
+    }
+
+    /// Skip a block comment like `#[ ... ]#` after its `#`, counting the comments inside it
+    fn block_comment(chars: &mut Cursor, line: &mut usize) -> Result<()> {
+        let (start, column) = (*line, chars.column - 1);
+        chars.next();
+        let mut depth = 1;
+        while let Some(c) = chars.next() {
+            match c {
+                '\n' => *line += 1,
+                '#' if chars.peek() == Some('[') => {
+                    chars.next();
+                    depth += 1;
+                }
+                ']' if chars.peek() == Some('#') => {
+                    chars.next();
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                }
+                _ => {}
+            }
+        }
+        Err(Error::at("Unclosed block comment", start, column))
+    }
+
+    /// Read up to the end of the line, leaving the newline
+    fn rest_of_line(chars: &mut Cursor) -> String {
+        let mut text = String::new();
+        while let Some(c) = chars.peek().filter(|&c| c != '\n') {
+            chars.next();
+            text.push(c);
+        }
+        text.trim_end().to_string()
+    }
+
     pub fn lex(&self) -> Result<Stream> {
+        match self.lex_all() {
+            (stream, errors) if errors.is_empty() => Ok(stream),
+            (_, errors) => Err(Error::many(errors)),
+        }
+    }
+
+    /// Lex the whole source, going on after errors to find all of them.
+    /// Bad tokens are replaced by `null`, so that cooking reports no errors caused by them.
+    /// Stray closing deliminators are left out of the stream, and it stops before the line
+    /// of the first unclosed one, so that it can still be sliced and cooked for more errors.
+    pub(super) fn lex_all(&self) -> (Stream, Vec<Error>) {
         let mut stream = Vec::new();
+        let mut errors = Vec::new();
 
-        let mut chars = self.0.chars().chain("\n".chars()).peekable();
+        let (mut line, column) = self.1;
+        let mut chars = Cursor {
+            chars: self.0.chars().chain("\n".chars()).peekable(),
+            column,
+        };
+        if line > 1 {
+            stream.push(Token::End(line));
+        }
 
         let mut status = Status::Normal;
         let mut buffer = String::new();
-        let mut line = 1;
         let mut unclosed = Vec::new();
+        // Documentation waiting for the `fn` or `map` of the next assignment
+        let mut doc: Option<String> = None;
 
-        while let Some(&c) = chars.peek() {
+        while let Some(c) = chars.peek() {
+            let len = stream.len();
             let used = match status {
                 Status::Normal => match c {
                     ' ' | '\t' | '\r' => true,
@@ -32,46 +609,112 @@ impl<'s> Compilable<'s> {
                         true
                     }
                     '(' | '[' | '{' => {
-                        unclosed.push((stream.len(), Enclosing::from(c)));
+                        unclosed.push((stream.len(), Enclosing::from(c), line, chars.column));
                         stream.push(Token::Null);
                         true
                     }
                     ')' | ']' | '}' => {
-                        if let Some((index, left)) = unclosed.pop() {
+                        if let Some((index, left, ..)) = unclosed.pop() {
                             if left != Enclosing::from(c) {
-                                return Err(Error::new(
+                                errors.push(Error::at(
                                     format!(
                                         "Unmatched previous deliminator {:?} and {:?}",
-                                        left.to_left(),
+                                        left.clone().to_left(),
                                         c
                                     ),
                                     line,
+                                    chars.column,
                                 ));
                             }
                             *stream.get_mut(index).unwrap() =
                                 Token::Enter(stream.len() - index, left);
-                            true
                         } else {
-                            return Err(Error::new(format!("Unclosed deliminator {:?}", c), line));
+                            errors.push(Error::at(
+                                format!("Unexpected closing deliminator {:?}", c),
+                                line,
+                                chars.column,
+                            ));
                         }
+                        true
                     }
-                    '0'..='9' | '-' => {
-                        status = Status::Int;
-                        buffer.push(c);
+                    '-' if !chars.starts_number(1) => {
+                        stream.push(Token::Neg);
                         true
                     }
+                    '0'..='9' | '-' | '.' if c != '.' || chars.starts_number(1) => {
+                        match Self::number(&mut chars, line) {
+                            Ok(token) => stream.push(token),
+                            Err(err) => {
+                                errors.push(err);
+                                // Skip the rest of the literal and stand in for it
+                                while chars
+                                    .peek()
+                                    .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '.')
+                                {
+                                    chars.next();
+                                }
+                                stream.push(Token::Null);
+                            }
+                        }
+                        false
+                    }
                     '\"' => {
-                        status = Status::Str(false, false);
-                        true
+                        chars.next();
+                        let triple = chars.sees("\"\"");
+                        if triple {
+                            chars.nth(1);
+                        }
+                        match Self::string(&mut chars, &mut line, triple, false, &mut errors) {
+                            Ok(token) => stream.push(token),
+                            Err(err) => {
+                                errors.push(err);
+                                stream.push(Token::Null);
+                            }
+                        }
+                        // The closing quotes are consumed already
+                        false
+                    }
+                    '\'' => {
+                        chars.next();
+                        match Self::char_literal(&mut chars, line) {
+                            Ok(c) => stream.push(Token::Uint(c as Uint)),
+                            Err(err) => {
+                                errors.push(err);
+                                stream.push(Token::Null);
+                            }
+                        }
+                        // The closing quote is consumed already
+                        false
                     }
-                    'A'..='Z' | 'a'..='z' | '_' => {
+                    _ if is_name_start(c) => {
                         status = Status::Word;
                         buffer.push(c);
                         true
                     }
                     '#' => {
-                        status = Status::Comment;
-                        true
+                        chars.next();
+                        match chars.peek() {
+                            Some('[') => {
+                                if let Err(err) = Self::block_comment(&mut chars, &mut line) {
+                                    errors.push(err);
+                                }
+                            }
+                            // Lines of three or more `#` are plain comments
+                            Some('#') if chars.peek_at(1) != Some('#') => {
+                                chars.next();
+                                let text = Self::rest_of_line(&mut chars);
+                                let text = text.strip_prefix(' ').unwrap_or(&text);
+                                match doc.as_mut() {
+                                    Some(doc) => {
+                                        doc.push('\n');
+                                        doc.push_str(text);
+                                    }
+                                    None => doc = Some(text.to_string()),
+                                }
+                            }
+                            _ => status = Status::Comment,
+                        }
+                        false
                     }
                     '.' => {
                         stream.push(Token::Dot);
@@ -90,122 +733,63 @@ impl<'s> Compilable<'s> {
                         true
                     }
                     _ => {
-                        return Err(Error::new(
+                        errors.push(Error::at(
                             format!("Unexpected stray character when lexing: '{}'", c),
                             line,
-                        ))
-                    }
-                },
-                Status::Int => match c {
-                    '0'..='9' => {
-                        buffer.push(c);
+                            chars.column,
+                        ));
+                        stream.push(Token::Null);
                         true
                     }
-                    '.' => {
+                },
+                Status::Word => match c {
+                    _ if is_xid_continue(c) => {
                         buffer.push(c);
-                        status = Status::Float;
                         true
                     }
-                    'u' => {
-                        let num: Uint = buffer.parse().map_err(|err| {
-                            Error::with_source(
-                                err,
-                                format!("When parsing unsigned integer {:?}", buffer),
-                            )
-                        })?;
-                        stream.push(Token::Uint(num));
+                    // Raw names like `r#map` are never keywords
+                    '#' if buffer == "r" && chars.peek_at(1).is_some_and(is_name_start) => {
+                        chars.next();
                         buffer.clear();
-                        status = Status::Normal;
-                        true
-                    }
-                    _ => {
-                        if buffer.ends_with('-') {
-                            stream.push(Token::Neg);
-                        } else {
-                            let num: Int = buffer.parse().map_err(|err| {
-                                Error::with_source(
-                                    err,
-                                    format!("When parsing integer {:?}", buffer),
-                                )
-                            })?;
-                            stream.push(Token::Int(num));
+                        while let Some(c) = chars.peek().filter(|&c| is_xid_continue(c)) {
+                            chars.next();
+                            buffer.push(c);
                         }
+                        stream.push(Token::Word(std::mem::take(&mut buffer)));
                         status = Status::Normal;
-                        buffer.clear();
                         false
                     }
-                },
-                Status::Float => match c {
-                    '0'..='9' => {
-                        buffer.push(c);
-                        true
-                    }
-                    _ => {
-                        if buffer.ends_with('.') {
-                            let num: Int = buffer[0..buffer.len() - 1].parse().map_err(|err| {
-                                Error::with_source(
-                                    err,
-                                    format!("When parsing integer {:?}", buffer),
-                                )
-                            })?;
-                            stream.push(Token::Int(num));
-                            stream.push(Token::Dot);
-                        } else {
-                            let num: Float = buffer.parse().map_err(|err| {
-                                Error::with_source(
-                                    err,
-                                    format!("When parsing floating number {:?}", buffer),
-                                )
-                            })?;
-                            stream.push(Token::Float(num));
+                    // Interpolated strings like `f"..."`
+                    '"' if buffer == "f" => {
+                        chars.next();
+                        let triple = chars.sees("\"\"");
+                        if triple {
+                            chars.nth(1);
+                        }
+                        match Self::string(&mut chars, &mut line, triple, true, &mut errors) {
+                            Ok(token) => stream.push(token),
+                            Err(err) => {
+                                errors.push(err);
+                                stream.push(Token::Null);
+                            }
                         }
                         buffer.clear();
                         status = Status::Normal;
+                        // The closing quotes are consumed already
                         false
                     }
-                },
-                Status::Str(is_escape, is_raw_string) => {
-                    if c == '\n' {
-                        line += 1;
-                    }
-                    if is_escape {
-                        let c = if is_raw_string {
-                            buffer.push('\\');
-                            c
-                        } else {
-                            match c {
-                                'n' => '\n',
-                                'r' => '\r',
-                                't' => '\t',
-                                _ => c,
-                            }
-                        };
-                        buffer.push(c);
-                        status = Status::Str(false, is_raw_string);
-                        true
-                    } else {
-                        match c {
-                            '\\' => {
-                                status = Status::Str(true, is_raw_string);
-                            }
-                            '\"' if !is_raw_string => {
-                                stream.push(Token::Str(std::mem::take(&mut buffer)));
-                                status = Status::Normal;
-                            }
-                            '%' => {
-                                status = Status::Str(false, !is_raw_string);
-                            }
-                            _ => {
-                                buffer.push(c);
+                    '"' | '#' if buffer == "r" && chars.sees_raw() => {
+                        match Self::raw_string(&mut chars, &mut line) {
+                            Ok(str) => stream.push(Token::Str(str)),
+                            Err(err) => {
+                                errors.push(err);
+                                stream.push(Token::Null);
                             }
                         }
-                        true
-                    }
-                }
-                Status::Word => match c {
-                    'A'..='Z' | 'a'..='z' | '_' => {
-                        buffer.push(c);
-                        true
+                        buffer.clear();
+                        status = Status::Normal;
+                        // The closing quote is consumed already
+                        false
                     }
                     _ => {
                         match buffer.as_str() {
@@ -238,10 +822,10 @@ impl<'s> Compilable<'s> {
                     }
                 },
                 Status::Comment => match c {
+                    // The newline still ends the line
                     '\n' => {
-                        line += 1;
                         status = Status::Normal;
-                        true
+                        false
                     }
                     _ => true,
                 },
@@ -250,8 +834,31 @@ impl<'s> Compilable<'s> {
             if used {
                 chars.next();
             }
+
+            if doc.is_some() && stream.len() > len {
+                match stream[len] {
+                    Token::Fn | Token::Map => stream.insert(len, Token::Doc(doc.take().unwrap())),
+                    Token::Word(_) | Token::Dot | Token::Asn | Token::End(_) => {}
+                    _ => doc = None,
+                }
+            }
+        }
+
+        if let Some(&(index, ..)) = unclosed.first() {
+            let line_start = stream[..index]
+                .iter()
+                .rposition(|token| matches!(token, Token::End(_)))
+                .unwrap_or(0);
+            stream.truncate(line_start);
+        }
+        for (_, left, line, column) in unclosed {
+            errors.push(Error::at(
+                format!("Unclosed deliminator {:?}", left.to_left()),
+                line,
+                column,
+            ));
         }
 
-        Ok(Stream(stream))
+        (Stream(stream), errors)
     }
 }
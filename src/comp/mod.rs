@@ -5,4 +5,6 @@ mod types;
 mod util;
 
 pub(super) use crate::prelude::*;
-pub use types::{Compilable, Cooked, Enclosing, Operator, Slice, Sliced, Stmt, Stream, Token};
+pub use types::{
+    Compilable, Cooked, Enclosing, Fragment, Operator, Slice, Sliced, Stmt, Stream, Token,
+};
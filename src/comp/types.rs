@@ -23,12 +23,19 @@ pub enum Token {
     Int(Int),
     Float(Float),
     Uint(Uint),
+    #[cfg(feature = "bigint")]
+    Big(Big),
     Bool(Bool),
     Null,
     Str(String),
+    /// String with interpolated expressions
+    Interp(Rc<Vec<Fragment>>),
 
     Stop,
 
+    /// Documentation from `##` comments, put before the `fn` or `map` it belongs to
+    Doc(String),
+
     Dot,
     Use,
     Import,
@@ -51,6 +58,13 @@ pub enum Token {
     Asn,
 }
 
+/// Part of a string with interpolation
+#[derive(Debug, Clone)]
+pub enum Fragment {
+    Str(String),
+    Expr(Stmt),
+}
+
 #[derive(Debug, Clone)]
 pub enum Slice {
     End(usize),
@@ -72,6 +86,7 @@ pub enum Stmt {
     Extern(Box<Stmt>),
     Map(Box<Stmt>),
     Fn(Rc<Stmt>),
+    Doc(String, Box<Stmt>),
     Neg(Box<Stmt>),
     Move(Box<Stmt>),
     Acq(Box<Stmt>),
@@ -88,7 +103,8 @@ pub enum Stmt {
     Asn(Box<Stmt>, Box<Stmt>),
 }
 
-pub struct Compilable<'s>(pub &'s str);
+/// Source to compile, with the line and column it starts at
+pub struct Compilable<'s>(pub &'s str, pub (usize, usize));
 
 pub struct Stream(pub Vec<Token>);
 
@@ -114,6 +130,7 @@ impl Token {
             Self::Import | Self::Include | Self::Extern => 3,
             Self::Map => 4,
             Self::Fn => 5,
+            Self::Doc(_) => 6,
             Self::Neg => 10,
             Self::Move | Self::Acq | Self::Return => 15,
             Self::Call => 20,
@@ -140,6 +157,7 @@ impl Token {
             | Self::Include
             | Self::Extern
             | Self::Fn
+            | Self::Doc(_)
             | Self::Map
             | Self::Neg
             | Self::Move
@@ -168,6 +186,7 @@ impl Token {
     }
 
     pub fn to_stmt_unary_fn(&self) -> fn(Box<Stmt>) -> Stmt {
+        #[allow(clippy::boxed_local)]
         fn fn_fn(stmt: Box<Stmt>) -> Stmt {
             Stmt::Fn(Rc::new(*stmt))
         }
@@ -187,6 +206,14 @@ impl Token {
         }
     }
 
+    /// Apply a unary operator to its operand
+    pub fn to_unary_stmt(self, operand: Box<Stmt>) -> Stmt {
+        match self {
+            Self::Doc(doc) => Stmt::Doc(doc, operand),
+            _ => self.to_stmt_unary_fn()(operand),
+        }
+    }
+
     pub fn is_op(&self) -> bool {
         self.priority() != 0
     }
@@ -222,11 +249,23 @@ impl Enclosing {
 
 impl<'s> Compilable<'s> {
     pub fn new(str: &'s str) -> Self {
-        Self(str)
+        Self(str, (1, 1))
     }
 
+    /// Source found at the given line and column of another one, for errors to point there
+    pub fn at(str: &'s str, line: usize, column: usize) -> Self {
+        Self(str, (line, column))
+    }
+
+    /// Compile the source, reporting all errors found in it
     pub fn compile(self) -> Result<Stmt> {
-        Ok(self.lex()?.slice().cook()?.0)
+        let (stream, mut errors) = self.lex_all();
+        match stream.slice().cook() {
+            Ok(cooked) if errors.is_empty() => return Ok(cooked.0),
+            Ok(_) => {}
+            Err(err) => errors.push(err),
+        }
+        Err(Error::many(errors))
     }
 }
 
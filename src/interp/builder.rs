@@ -0,0 +1,109 @@
+use super::*;
+
+/// Configures the modules, search paths and output sinks of an [`Interpreter`]
+pub struct InterpreterBuilder {
+    modules: Option<Vec<String>>,
+    paths: Vec<PathBuf>,
+    read_env: bool,
+    stdout: Option<Box<dyn Write>>,
+    stderr: Option<Box<dyn Write>>,
+}
+
+impl InterpreterBuilder {
+    /// Start with every compiled-in module, reading `LEAS_PATH` for module search paths
+    pub fn new() -> Self {
+        Self {
+            modules: None,
+            paths: Vec::new(),
+            read_env: true,
+            stdout: None,
+            stderr: None,
+        }
+    }
+
+    /// Enable only the given module, together with others enabled by this function
+    pub fn module(mut self, name: impl ToString) -> Self {
+        self.modules
+            .get_or_insert_with(Vec::new)
+            .push(name.to_string());
+        self
+    }
+
+    pub fn modules<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: ToString,
+    {
+        for name in names {
+            self = self.module(name);
+        }
+        self
+    }
+
+    /// Disable all modules, except for the ones enabled later with `module`
+    pub fn no_modules(mut self) -> Self {
+        self.modules = Some(Vec::new());
+        self
+    }
+
+    /// Add a directory to search when importing modules
+    pub fn path(mut self, path: impl AsRef<Path>) -> Self {
+        self.paths.push(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Whether to read `LEAS_PATH` and `LD_LIBRARY_PATH` from the process environment
+    pub fn read_env(mut self, read_env: bool) -> Self {
+        self.read_env = read_env;
+        self
+    }
+
+    pub fn stdout(mut self, w: impl Write + 'static) -> Self {
+        self.stdout = Some(Box::new(w));
+        self
+    }
+
+    pub fn stderr(mut self, w: impl Write + 'static) -> Self {
+        self.stderr = Some(Box::new(w));
+        self
+    }
+
+    pub fn build(self) -> Result<Interpreter> {
+        let mut env = if self.read_env {
+            Env::read()
+        } else {
+            Env::new()
+        };
+        env.modules.extend(self.paths);
+        if let Some(stdout) = self.stdout {
+            env.set_stdout(stdout);
+        }
+        if let Some(stderr) = self.stderr {
+            env.set_stderr(stderr);
+        }
+
+        let mut map = Map::with_env(env);
+        match self.modules {
+            Some(modules) => {
+                for name in modules {
+                    if !crate::modules::init(&mut map, &name) {
+                        return Err(Error::plain(format!(
+                            "Module {:?} is not available, expected one of {:?}",
+                            name,
+                            crate::modules::names().collect::<Vec<_>>()
+                        )));
+                    }
+                }
+            }
+            None => crate::modules::init_all(&mut map),
+        }
+
+        Ok(Interpreter { map })
+    }
+}
+
+impl Default for InterpreterBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,99 @@
+mod builder;
+
+pub(super) use crate::prelude::*;
+pub use builder::InterpreterBuilder;
+
+use rt::Eval;
+
+/// A ready-to-use leas runtime with its global map and enabled modules
+#[derive(Debug)]
+pub struct Interpreter {
+    map: Map,
+}
+
+impl Interpreter {
+    /// Create an interpreter with every compiled-in module and the default environment
+    pub fn new() -> Self {
+        let mut map = Map::new();
+        crate::modules::init_all(&mut map);
+        Self { map }
+    }
+
+    pub fn builder() -> InterpreterBuilder {
+        InterpreterBuilder::new()
+    }
+
+    /// Compile and evaluate the source in the global map
+    pub fn run_str(&mut self, source: &str) -> Result<Value> {
+        let stmt = Compilable::new(source).compile()?;
+        let result = stmt.eval(&mut self.map)?;
+        Self::acquire(result, self.map.line())
+    }
+
+    /// Compile and evaluate a file in the global map, resolving imports relative to the file
+    pub fn run_file(&mut self, path: impl AsRef<Path>) -> Result<Value> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .map_err(|err| Error::with_source(err, format!("When reading file {:?}", path)))?;
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+        self.map.env().forward_base(path.clone());
+        let result = Compilable::new(&content)
+            .compile()
+            .map_err(|err| err.with(format!("When compiling file {:?}", path)))
+            .and_then(|stmt| {
+                stmt.eval(&mut self.map)
+                    .map_err(|err| err.with(format!("When evaluating file {:?}", path)))
+            });
+        self.map.env().backward_base();
+
+        Self::acquire(result?, self.map.line())
+    }
+
+    /// Get a global value by name, converted to a Rust value
+    pub fn get_global<T: FromValue>(&self, name: &str) -> Result<T> {
+        Self::acquire(self.map.req(name)?, self.map.line())?.extract(self.map.line())
+    }
+
+    pub fn set_global(&mut self, name: impl ToString, value: impl IntoValue) {
+        let value = value.into_value(&self.map);
+        self.map.set(name.to_string(), value);
+    }
+
+    /// Call the global function `name` with the given arguments, converting the result to a Rust value.
+    /// Tuples are passed as a list, just like `f(a, b)` in scripts.
+    /// The arguments are kept until the result is converted, so a function may return them,
+    /// but values local to the function are deleted when it returns unless it returns them with `move`
+    pub fn call<T: FromValue>(&mut self, name: &str, args: impl IntoValue) -> Result<T> {
+        let f = self.map.req(name)?;
+        let args = args.into_value(&self.map);
+        let result = f.call(&mut self.map, args.clone()).ok_or_else(|| {
+            Error::new(
+                format!("Cannot call value {} named {:?}", f, name),
+                self.map.line(),
+            )
+        })??;
+        Self::acquire(result, self.map.line())?.extract(self.map.line())
+    }
+
+    pub fn map(&self) -> &Map {
+        &self.map
+    }
+
+    pub fn map_mut(&mut self) -> &mut Map {
+        &mut self.map
+    }
+
+    /// Values returned from scripts may be weak references into the map, so they are upgraded here
+    fn acquire(value: Value, line: usize) -> Result<Value> {
+        value
+            .upgrade()
+            .ok_or_else(|| Error::new("Attempted to acquire deleted value", line))
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
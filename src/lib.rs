@@ -1,5 +1,8 @@
 pub mod comp;
+pub mod interp;
 pub mod modules;
 pub mod prelude;
 pub mod rt;
 pub mod sys;
+
+pub use interp::{Interpreter, InterpreterBuilder};
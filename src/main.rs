@@ -1,5 +1,5 @@
 use leas::prelude::*;
-use rt::Eval;
+use leas::Interpreter;
 
 fn open<T>(result: sys::Result<T>) -> T {
     match result {
@@ -12,24 +12,10 @@ fn open<T>(result: sys::Result<T>) -> T {
 }
 
 fn main() {
-    let content = std::fs::read_to_string("tests/main.lea").unwrap();
-    let comp = comp::Compilable::new(&content);
-    let stream = open(comp.lex());
-    //println!("Output stream: {:?}", stream.0);
-    let sliced = stream.slice();
-    //println!("Output slice: {:?}", sliced.0);
-    let cooked = open(sliced.cook());
-    //println!("Output cooked: {:?}", cooked.0);
-    let mut map = sys::Map::new();
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "tests/main.lea".to_string());
+    let mut interpreter = Interpreter::new();
 
-    leas::modules::bool::init(&mut map);
-    leas::modules::float::init(&mut map);
-    leas::modules::int::init(&mut map);
-    leas::modules::str::init(&mut map);
-    leas::modules::sys::init(&mut map);
-    leas::modules::toml::init(&mut map);
-    leas::modules::uint::init(&mut map);
-    leas::modules::vec::init(&mut map);
-
-    let _result = open(cooked.0.eval(&mut map));
+    let _result = open(interpreter.run_file(path));
 }
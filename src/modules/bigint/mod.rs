@@ -0,0 +1,173 @@
+//! Arbitrary-precision integers
+//!
+//! Big integers are immutable resources, written in scripts with an `n` suffix like `12n`.
+//! Every function taking a big integer also accepts an int or a uint.
+
+use super::num::radix_of;
+use crate::prelude::*;
+use num_bigint::BigUint;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use std::cmp::Ordering;
+
+impl FromValue for Big {
+    fn type_name() -> Cow<'static, str> {
+        Cow::Borrowed("bigint")
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Int(n) => Some(Big::from(*n)),
+            Value::Uint(n) => Some(Big::from(*n)),
+            _ => value.as_res()?.visit(|n: &Big| n.clone()),
+        }
+    }
+}
+
+impl IntoValue for Big {
+    fn into_value(self, _: &Map) -> Value {
+        Value::Res(Resource::new(self))
+    }
+}
+
+fn from(n: Big) -> Result<Big> {
+    Ok(n)
+}
+
+fn add(a: Big, b: Big) -> Result<Big> {
+    Ok(a + b)
+}
+
+fn sub(a: Big, b: Big) -> Result<Big> {
+    Ok(a - b)
+}
+
+fn mul(a: Big, b: Big) -> Result<Big> {
+    Ok(a * b)
+}
+
+fn div(a: Big, b: Big) -> Result<Big> {
+    if b.is_zero() {
+        return Err(Error::plain(format!(
+            "Division by zero when computing {} / 0",
+            a
+        )));
+    }
+    Ok(a / b)
+}
+
+fn rem(a: Big, b: Big) -> Result<Big> {
+    if b.is_zero() {
+        return Err(Error::plain(format!(
+            "Division by zero when computing {} % 0",
+            a
+        )));
+    }
+    Ok(a % b)
+}
+
+/// Largest result of `pow` in bits, about twenty million decimal digits
+const MAX_POW_BITS: u64 = 1 << 26;
+
+fn pow(a: Big, b: Uint) -> Result<Big> {
+    // 0, 1 and -1 stay as small whatever the exponent, of which only the parity matters
+    if a.magnitude() <= &BigUint::one() {
+        let b = if b == 0 { 0 } else { 2 - b % 2 };
+        return Ok(a.pow(b as u32));
+    }
+    // The result has at least this many bits, which also keeps `b` below `u32::MAX`
+    let bits = (a.bits() - 1).saturating_mul(b).saturating_add(1);
+    if bits > MAX_POW_BITS {
+        return Err(Error::plain(format!(
+            "Cannot compute a big integer of {} bits to the power {}, the limit is {} bits",
+            a.bits(),
+            b,
+            MAX_POW_BITS
+        )));
+    }
+    Ok(a.pow(b as u32))
+}
+
+fn neg(n: Big) -> Result<Big> {
+    Ok(-n)
+}
+
+fn abs(n: Big) -> Result<Big> {
+    Ok(n.abs())
+}
+
+fn eq(a: Big, b: Big) -> Result<Bool> {
+    Ok(a == b)
+}
+
+fn lt(a: Big, b: Big) -> Result<Bool> {
+    Ok(a < b)
+}
+
+fn cmp(a: Big, b: Big) -> Result<Int> {
+    Ok(match a.cmp(&b) {
+        Ordering::Less => -1,
+        Ordering::Equal => 0,
+        Ordering::Greater => 1,
+    })
+}
+
+fn parse(s: String) -> Result<Option<Big>> {
+    Ok(s.trim().parse().ok())
+}
+
+fn parse_radix(s: String, radix: Uint) -> Result<Option<Big>> {
+    Ok(Big::parse_bytes(s.trim().as_bytes(), radix_of(radix)?))
+}
+
+fn to_str(n: Big) -> Result<String> {
+    Ok(n.to_string())
+}
+
+fn to_str_radix(n: Big, radix: Uint) -> Result<String> {
+    Ok(n.to_str_radix(radix_of(radix)?))
+}
+
+fn to_int(n: Big) -> Result<Int> {
+    n.to_i64()
+        .ok_or_else(|| Error::plain(format!("Big integer {} is out of range of int", n)))
+}
+
+fn to_uint(n: Big) -> Result<Uint> {
+    n.to_u64()
+        .ok_or_else(|| Error::plain(format!("Big integer {} is out of range of uint", n)))
+}
+
+fn to_float(n: Big) -> Result<Float> {
+    Ok(n.to_f64().unwrap_or(Float::NAN))
+}
+
+fn init_module(map: &mut Map) {
+    map.register_fn("from", from);
+    map.register_fn("add", add);
+    map.register_fn("sub", sub);
+    map.register_fn("mul", mul);
+    map.register_fn("div", div);
+    map.register_fn("mod", rem);
+    map.register_fn("pow", pow);
+    map.register_fn("neg", neg);
+    map.register_fn("abs", abs);
+    map.register_fn("eq", eq);
+    map.register_fn("lt", lt);
+    map.register_fn("cmp", cmp);
+    map.register_fn("parse", parse);
+    map.register_fn("parse_radix", parse_radix);
+    map.register_fn("to_str", to_str);
+    map.register_fn("to_str_radix", to_str_radix);
+    map.register_fn("to_int", to_int);
+    map.register_fn("to_uint", to_uint);
+    map.register_fn("to_float", to_float);
+    map.register_class(
+        Class::builder::<Big>("bigint")
+            .method("to_str", |n, map, _| Ok(n.to_string().into_value(map)))
+            .build(),
+    );
+}
+
+pub fn init(map: &mut Map) {
+    map.register_init("bigint", init_module);
+}
@@ -0,0 +1,101 @@
+//! Characters
+//!
+//! Characters are uints holding a Unicode code point, written in scripts as `'a'` or `'\n'`.
+//! Functions taking a character fail on uints which are not a valid code point.
+
+use super::num::radix_of;
+use crate::prelude::*;
+
+macro_rules! predicate {
+    ($($name: ident => $method: ident),*) => {$(
+        fn $name(c: char) -> Result<Bool> {
+            Ok(c.$method())
+        }
+    )*};
+}
+
+predicate!(
+    is_digit => is_ascii_digit,
+    is_numeric => is_numeric,
+    is_alpha => is_alphabetic,
+    is_alnum => is_alphanumeric,
+    is_whitespace => is_whitespace,
+    is_upper => is_uppercase,
+    is_lower => is_lowercase,
+    is_control => is_control,
+    is_punct => is_ascii_punctuation,
+    is_ascii => is_ascii
+);
+
+fn is_char(n: Uint) -> Result<Bool> {
+    Ok(u32::try_from(n).ok().and_then(char::from_u32).is_some())
+}
+
+/// Characters whose case maps to several characters, like `ß`, are kept as they are
+fn to_upper(c: char) -> Result<char> {
+    let mut upper = c.to_uppercase();
+    Ok(if upper.len() == 1 {
+        upper.next().unwrap()
+    } else {
+        c
+    })
+}
+
+fn to_lower(c: char) -> Result<char> {
+    let mut lower = c.to_lowercase();
+    Ok(if lower.len() == 1 {
+        lower.next().unwrap()
+    } else {
+        c
+    })
+}
+
+fn to_digit(c: char, radix: Uint) -> Result<Option<Uint>> {
+    Ok(c.to_digit(radix_of(radix)?).map(Uint::from))
+}
+
+fn from_digit(n: Uint, radix: Uint) -> Result<Option<char>> {
+    let radix = radix_of(radix)?;
+    Ok(u32::try_from(n)
+        .ok()
+        .and_then(|n| char::from_digit(n, radix)))
+}
+
+fn to_str(c: char) -> Result<String> {
+    Ok(c.to_string())
+}
+
+/// The only character of the string, stop if it has none or more than one
+fn from_str(s: String) -> Result<Option<char>> {
+    let mut chars = s.chars();
+    Ok(chars.next().filter(|_| chars.next().is_none()))
+}
+
+fn byte_length(c: char) -> Result<Uint> {
+    Ok(c.len_utf8() as Uint)
+}
+
+fn init_module(map: &mut Map) {
+    map.register_fn("is_digit", is_digit);
+    map.register_fn("is_numeric", is_numeric);
+    map.register_fn("is_alpha", is_alpha);
+    map.register_fn("is_alnum", is_alnum);
+    map.register_fn("is_whitespace", is_whitespace);
+    map.register_fn("is_upper", is_upper);
+    map.register_fn("is_lower", is_lower);
+    map.register_fn("is_control", is_control);
+    map.register_fn("is_punct", is_punct);
+    map.register_fn("is_ascii", is_ascii);
+    map.register_fn("is_char", is_char);
+    map.register_fn("to_upper", to_upper);
+    map.register_fn("to_lower", to_lower);
+    map.register_fn("to_digit", to_digit);
+    map.register_fn("from_digit", from_digit);
+    map.register_fn("to_str", to_str);
+    map.register_fn("from_str", from_str);
+    map.register_fn("byte_length", byte_length);
+}
+
+pub fn init(map: &mut Map) {
+    map.register_init("char", init_module);
+}
@@ -0,0 +1,286 @@
+//! Dictionaries keyed by arbitrary hashable values
+//!
+//! Keys can be ints, uints, bools, strings and tuples (vectors) of those.
+//! Entries keep their insertion order, which is also the iteration order.
+
+use crate::prelude::*;
+
+/// A value usable as a dictionary key
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Key {
+    Int(Int),
+    Uint(Uint),
+    Bool(Bool),
+    Str(String),
+    Tuple(Vec<Key>),
+}
+
+pub type Dict = IndexMap<Key, Value>;
+
+impl Key {
+    /// Convert a value into a key, `None` if it is not hashable
+    pub fn from_value(value: &Value) -> Option<Self> {
+        Self::from_value_guarded(value, &mut Vec::new())
+    }
+
+    fn from_value_guarded(value: &Value, visiting: &mut Vec<*const ()>) -> Option<Self> {
+        match value {
+            Value::Int(value) => Some(Self::Int(*value)),
+            Value::Uint(value) => Some(Self::Uint(*value)),
+            Value::Bool(value) => Some(Self::Bool(*value)),
+            Value::Res(_) | Value::Weak(_) => {
+                let res = value.as_res()?;
+                if let Some(str) = res.visit(|str: &String| str.clone()) {
+                    return Some(Self::Str(str));
+                }
+                if visiting.contains(&res.as_ptr()) {
+                    return None;
+                }
+                visiting.push(res.as_ptr());
+                let result = res.visit(|vec: &VecDeque<Value>| {
+                    vec.iter()
+                        .map(|value| Self::from_value_guarded(value, visiting))
+                        .collect::<Option<Vec<_>>>()
+                        .map(Self::Tuple)
+                });
+                visiting.pop();
+                result?
+            }
+            _ => None,
+        }
+    }
+
+    pub fn to_value(&self) -> Value {
+        match self {
+            Self::Int(value) => Value::Int(*value),
+            Self::Uint(value) => Value::Uint(*value),
+            Self::Bool(value) => Value::Bool(*value),
+            Self::Str(str) => Value::Res(Resource::new(str.clone())),
+            Self::Tuple(keys) => Value::Res(Resource::new(
+                keys.iter().map(Self::to_value).collect::<VecDeque<_>>(),
+            )),
+        }
+    }
+}
+
+fn key_of(value: &Value, line: usize) -> Result<Key> {
+    Key::from_value(value).ok_or_else(|| {
+        Error::new(
+            format!("Argument key cannot be used as a dict key, found {}", value),
+            line,
+        )
+    })
+}
+
+fn not_dict(line: usize) -> impl FnOnce() -> Error + Clone {
+    move || Error::new("Argument dict is not a dictionary", line)
+}
+
+fn empty(_: &mut Map, _: Value) -> Result<Value> {
+    Ok(Value::Res(Resource::new(Dict::new())))
+}
+
+fn get(map: &mut Map, arg: Value) -> Result<Value> {
+    let mut matcher = Matcher::listed().with_singles(&["dict", "key"]);
+    matcher.mat_or_err(arg, map.line())?;
+    let mut list = matcher.to_values().unwrap();
+
+    let dict = list.pop_front().unwrap();
+    let key = key_of(&list.pop_front().unwrap(), map.line())?;
+
+    dict.visit_res_or_else(
+        move |dict: &Dict| dict.get(&key).cloned().unwrap_or(Value::Stop),
+        not_dict(map.line()),
+    )
+}
+
+fn set(map: &mut Map, arg: Value) -> Result<Value> {
+    let mut matcher = Matcher::listed().with_singles(&["dict", "key", "value"]);
+    matcher.mat_or_err(arg, map.line())?;
+    let mut list = matcher.to_values().unwrap();
+
+    let dict = list.pop_front().unwrap();
+    let key = key_of(&list.pop_front().unwrap(), map.line())?;
+    let value = list.pop_front().unwrap();
+
+    dict.visit_mut_res_or_else(
+        move |dict: &mut Dict| {
+            dict.insert(key, value);
+        },
+        not_dict(map.line()),
+    )?;
+
+    Ok(Value::Null)
+}
+
+fn remove(map: &mut Map, arg: Value) -> Result<Value> {
+    let mut matcher = Matcher::listed().with_singles(&["dict", "key"]);
+    matcher.mat_or_err(arg, map.line())?;
+    let mut list = matcher.to_values().unwrap();
+
+    let dict = list.pop_front().unwrap();
+    let key = key_of(&list.pop_front().unwrap(), map.line())?;
+
+    dict.visit_mut_res_or_else(
+        move |dict: &mut Dict| dict.shift_remove(&key).unwrap_or(Value::Stop),
+        not_dict(map.line()),
+    )
+}
+
+fn has(map: &mut Map, arg: Value) -> Result<Value> {
+    let mut matcher = Matcher::listed().with_singles(&["dict", "key"]);
+    matcher.mat_or_err(arg, map.line())?;
+    let mut list = matcher.to_values().unwrap();
+
+    let dict = list.pop_front().unwrap();
+    let key = key_of(&list.pop_front().unwrap(), map.line())?;
+
+    dict.visit_res_or_else(
+        move |dict: &Dict| Value::Bool(dict.contains_key(&key)),
+        not_dict(map.line()),
+    )
+}
+
+fn keys(map: &mut Map, arg: Value) -> Result<Value> {
+    let mut matcher: Matcher = Matcher::single("dict");
+    matcher.mat_or_err(arg, map.line())?;
+
+    let dict = matcher.to_single().unwrap();
+
+    dict.visit_res_or_else(
+        |dict: &Dict| {
+            Value::Res(Resource::new(
+                dict.keys().map(Key::to_value).collect::<VecDeque<_>>(),
+            ))
+        },
+        not_dict(map.line()),
+    )
+}
+
+fn values(map: &mut Map, arg: Value) -> Result<Value> {
+    let mut matcher: Matcher = Matcher::single("dict");
+    matcher.mat_or_err(arg, map.line())?;
+
+    let dict = matcher.to_single().unwrap();
+
+    dict.visit_res_or_else(
+        |dict: &Dict| {
+            Value::Res(Resource::new(
+                dict.values().cloned().collect::<VecDeque<_>>(),
+            ))
+        },
+        not_dict(map.line()),
+    )
+}
+
+fn length(map: &mut Map, arg: Value) -> Result<Value> {
+    let mut matcher: Matcher = Matcher::single("dict");
+    matcher.mat_or_err(arg, map.line())?;
+
+    let dict = matcher.to_single().unwrap();
+
+    dict.visit_res_or_else(
+        |dict: &Dict| Value::Uint(dict.len() as Uint),
+        not_dict(map.line()),
+    )
+}
+
+fn clone(map: &mut Map, arg: Value) -> Result<Value> {
+    let mut matcher: Matcher = Matcher::single("dict");
+    matcher.mat_or_err(arg, map.line())?;
+
+    let dict = matcher.to_single().unwrap();
+
+    dict.visit_res_or_else(
+        |dict: &Dict| Value::Res(Resource::new(dict.clone())),
+        not_dict(map.line()),
+    )
+}
+
+/// The entry at a position in insertion order as a `(key, value)` tuple, used by iterators
+fn entry_at(map: &mut Map, arg: Value) -> Result<Value> {
+    let mut matcher = Matcher::listed().with_singles(&["dict", "index"]);
+    matcher.mat_or_err(arg, map.line())?;
+    let mut list = matcher.to_values().unwrap();
+
+    let dict = list.pop_front().unwrap();
+    let index = list.pop_front().unwrap();
+
+    let line = map.line();
+    let index = index
+        .as_uint()
+        .ok_or_else(|| Error::new("Argument index is not an unsigned integer", line))?
+        as usize;
+    dict.visit_res_or_else(
+        move |dict: &Dict| match dict.get_index(index) {
+            Some((key, value)) => Value::Res(Resource::new(VecDeque::from([
+                key.to_value(),
+                value.clone(),
+            ]))),
+            None => Value::Stop,
+        },
+        not_dict(line),
+    )
+}
+
+/// Replace the value at a position in insertion order, used by iterators
+fn set_at(map: &mut Map, arg: Value) -> Result<Value> {
+    let mut matcher = Matcher::listed().with_singles(&["dict", "index", "value"]);
+    matcher.mat_or_err(arg, map.line())?;
+    let mut list = matcher.to_values().unwrap();
+
+    let dict = list.pop_front().unwrap();
+    let index = list.pop_front().unwrap();
+    let value = list.pop_front().unwrap();
+
+    let line = map.line();
+    let index = index
+        .as_uint()
+        .ok_or_else(|| Error::new("Argument index is not an unsigned integer", line))?
+        as usize;
+    let found = dict.visit_mut_res_or_else(
+        move |dict: &mut Dict| match dict.get_index_mut(index) {
+            Some((_, entry)) => {
+                *entry = value;
+                true
+            }
+            None => false,
+        },
+        not_dict(line),
+    )?;
+    if !found {
+        return Err(Error::new(
+            format!("Index {} is out of bounds of the dictionary", index),
+            line,
+        ));
+    }
+
+    Ok(Value::Null)
+}
+
+fn init_module(map: &mut Map) {
+    map.register("empty", empty);
+    map.register("get", get);
+    map.register("set", set);
+    map.register("remove", remove);
+    map.register("has", has);
+    map.register("keys", keys);
+    map.register("values", values);
+    map.register("length", length);
+    map.register("clone", clone);
+    map.register("entry_at", entry_at);
+    map.register("set_at", set_at);
+    map.register_class(
+        Class::builder::<Dict>("dict")
+            .entries(|dict: &Dict| {
+                dict.iter()
+                    .map(|(key, value)| (key.to_value(), value.clone()))
+                    .collect()
+            })
+            .build(),
+    );
+}
+
+pub fn init(map: &mut Map) {
+    map.register_init("dict", init_module);
+}
@@ -1,110 +1,164 @@
+use super::num::{format_len, zero_pad};
 use crate::prelude::*;
 
-fn add(map: &mut Map, arg: Value) -> Result<Value> {
-    let mut matcher = Matcher::listed().with_singles(&["a", "b"]);
-    matcher.mat_or_err(arg, map.line())?;
-    let mut list = matcher.to_values().unwrap();
+fn add(a: Float, b: Float) -> Result<Float> {
+    Ok(a + b)
+}
 
-    let a = list.pop_front().unwrap();
-    let b = list.pop_front().unwrap();
+fn sub(a: Float, b: Float) -> Result<Float> {
+    Ok(a - b)
+}
 
-    a.as_float()
-        .and_then(|a| b.as_float().map(|b| a + b))
-        .map(Value::Float)
-        .ok_or_else(move || Error::new("Arguments a and b are not floats", map.line()))
+fn mul(a: Float, b: Float) -> Result<Float> {
+    Ok(a * b)
 }
 
-fn sub(map: &mut Map, arg: Value) -> Result<Value> {
-    let mut matcher = Matcher::listed().with_singles(&["a", "b"]);
-    matcher.mat_or_err(arg, map.line())?;
-    let mut list = matcher.to_values().unwrap();
+fn div(a: Float, b: Float) -> Result<Float> {
+    Ok(a / b)
+}
 
-    let a = list.pop_front().unwrap();
-    let b = list.pop_front().unwrap();
+fn eq(a: Float, b: Float) -> Result<Bool> {
+    Ok(a == b)
+}
 
-    a.as_float()
-        .and_then(|a| b.as_float().map(|b| a - b))
-        .map(Value::Float)
-        .ok_or_else(move || Error::new("Arguments a and b are not floats", map.line()))
+fn lt(a: Float, b: Float) -> Result<Bool> {
+    Ok(a < b)
 }
 
-fn mul(map: &mut Map, arg: Value) -> Result<Value> {
-    let mut matcher = Matcher::listed().with_singles(&["a", "b"]);
-    matcher.mat_or_err(arg, map.line())?;
-    let mut list = matcher.to_values().unwrap();
+macro_rules! unary {
+    ($($name: ident),*) => {$(
+        fn $name(n: Float) -> Result<Float> {
+            Ok(n.$name())
+        }
+    )*};
+}
 
-    let a = list.pop_front().unwrap();
-    let b = list.pop_front().unwrap();
+unary!(sqrt, exp, ln, log2, log10, sin, cos, tan, asin, acos, atan, floor, ceil, round, trunc, abs);
 
-    a.as_float()
-        .and_then(|a| b.as_float().map(|b| a * b))
-        .map(Value::Float)
-        .ok_or_else(move || Error::new("Arguments a and b are not floats", map.line()))
+fn pow(a: Float, b: Float) -> Result<Float> {
+    Ok(a.powf(b))
 }
 
-fn div(map: &mut Map, arg: Value) -> Result<Value> {
-    let mut matcher = Matcher::listed().with_singles(&["a", "b"]);
-    matcher.mat_or_err(arg, map.line())?;
-    let mut list = matcher.to_values().unwrap();
+fn log(n: Float, base: Float) -> Result<Float> {
+    Ok(n.log(base))
+}
 
-    let a = list.pop_front().unwrap();
-    let b = list.pop_front().unwrap();
+fn atan2(y: Float, x: Float) -> Result<Float> {
+    Ok(y.atan2(x))
+}
 
-    a.as_float()
-        .and_then(|a| b.as_float().map(|b| a / b))
-        .map(Value::Float)
-        .ok_or_else(move || Error::new("Arguments a and b are not floats", map.line()))
+fn min(a: Float, b: Float) -> Result<Float> {
+    Ok(a.min(b))
 }
 
-fn eq(map: &mut Map, arg: Value) -> Result<Value> {
-    let mut matcher = Matcher::listed().with_singles(&["a", "b"]);
-    matcher.mat_or_err(arg, map.line())?;
-    let mut list = matcher.to_values().unwrap();
+fn max(a: Float, b: Float) -> Result<Float> {
+    Ok(a.max(b))
+}
 
-    let a = list.pop_front().unwrap();
-    let b = list.pop_front().unwrap();
+fn clamp(n: Float, min: Float, max: Float) -> Result<Float> {
+    if min <= max {
+        Ok(n.clamp(min, max))
+    } else {
+        Err(Error::plain(format!(
+            "Cannot clamp to the range from {} to {}",
+            min, max
+        )))
+    }
+}
 
-    a.as_float()
-        .and_then(|a| b.as_float().map(|b| a == b))
-        .map(Value::Bool)
-        .ok_or_else(move || Error::new("Arguments a and b are not floats", map.line()))
+fn is_nan(n: Float) -> Result<Bool> {
+    Ok(n.is_nan())
 }
 
-fn lt(map: &mut Map, arg: Value) -> Result<Value> {
-    let mut matcher = Matcher::listed().with_singles(&["a", "b"]);
-    matcher.mat_or_err(arg, map.line())?;
-    let mut list = matcher.to_values().unwrap();
+fn is_finite(n: Float) -> Result<Bool> {
+    Ok(n.is_finite())
+}
+
+/// Truncate towards zero, failing for NaN and values out of range instead of saturating
+fn to_int(n: Float) -> Result<Int> {
+    // Both bounds are powers of two, so they are exact as floats
+    if Int::MIN as Float <= n && n < -(Int::MIN as Float) {
+        Ok(n as Int)
+    } else {
+        Err(Error::plain(format!("Float {} is out of range of int", n)))
+    }
+}
+
+fn to_uint(n: Float) -> Result<Uint> {
+    if -1.0 < n && n < Uint::MAX as Float {
+        Ok(n as Uint)
+    } else {
+        Err(Error::plain(format!("Float {} is out of range of uint", n)))
+    }
+}
+
+fn parse(s: String) -> Result<Option<Float>> {
+    Ok(s.trim().parse().ok())
+}
 
-    let a = list.pop_front().unwrap();
-    let b = list.pop_front().unwrap();
+fn to_str(n: Float) -> Result<String> {
+    Ok(n.to_string())
+}
 
-    a.as_float()
-        .and_then(|a| b.as_float().map(|b| a < b))
-        .map(Value::Bool)
-        .ok_or_else(move || Error::new("Arguments a and b are not floats", map.line()))
+fn to_str_fixed(n: Float, precision: Uint) -> Result<String> {
+    Ok(format!("{:.*}", format_len(precision, "Precision")?, n))
 }
 
-fn to_int(map: &mut Map, arg: Value) -> Result<Value> {
-    arg.as_float()
-        .map(|n| Value::Int(n as Int))
-        .ok_or_else(|| Error::new("Argument is not a float", map.line()))
+fn to_str_sci(n: Float, precision: Uint) -> Result<String> {
+    Ok(format!("{:.*e}", format_len(precision, "Precision")?, n))
 }
 
-fn to_uint(map: &mut Map, arg: Value) -> Result<Value> {
-    arg.as_float()
-        .map(|n| Value::Uint(n as Uint))
-        .ok_or_else(|| Error::new("Argument is not a float", map.line()))
+fn to_str_padded(n: Float, width: Uint, precision: Uint) -> Result<String> {
+    zero_pad(
+        format!("{:.*}", format_len(precision, "Precision")?, n),
+        width,
+    )
 }
 
 fn init_module(map: &mut Map) {
-    map.register("add", add);
-    map.register("sub", sub);
-    map.register("mul", mul);
-    map.register("div", div);
-    map.register("eq", eq);
-    map.register("lt", lt);
-    map.register("to_int", to_int);
-    map.register("to_uint", to_uint);
+    map.register_fn("add", add);
+    map.register_fn("sub", sub);
+    map.register_fn("mul", mul);
+    map.register_fn("div", div);
+    map.register_fn("eq", eq);
+    map.register_fn("lt", lt);
+    map.register_fn("to_int", to_int);
+    map.register_fn("to_uint", to_uint);
+    map.register_fn("sqrt", sqrt);
+    map.register_fn("pow", pow);
+    map.register_fn("exp", exp);
+    map.register_fn("ln", ln);
+    map.register_fn("log", log);
+    map.register_fn("log2", log2);
+    map.register_fn("log10", log10);
+    map.register_fn("sin", sin);
+    map.register_fn("cos", cos);
+    map.register_fn("tan", tan);
+    map.register_fn("asin", asin);
+    map.register_fn("acos", acos);
+    map.register_fn("atan", atan);
+    map.register_fn("atan2", atan2);
+    map.register_fn("floor", floor);
+    map.register_fn("ceil", ceil);
+    map.register_fn("round", round);
+    map.register_fn("trunc", trunc);
+    map.register_fn("abs", abs);
+    map.register_fn("min", min);
+    map.register_fn("max", max);
+    map.register_fn("clamp", clamp);
+    map.register_fn("is_nan", is_nan);
+    map.register_fn("is_finite", is_finite);
+
+    map.register_fn("parse", parse);
+    map.register_fn("to_str", to_str);
+    map.register_fn("to_str_fixed", to_str_fixed);
+    map.register_fn("to_str_sci", to_str_sci);
+    map.register_fn("to_str_padded", to_str_padded);
+
+    map.forced_set("pi".to_string(), Value::Float(std::f64::consts::PI));
+    map.forced_set("e".to_string(), Value::Float(std::f64::consts::E));
+    map.forced_set("inf".to_string(), Value::Float(Float::INFINITY));
+    map.forced_set("nan".to_string(), Value::Float(Float::NAN));
 }
 
 pub fn init(map: &mut Map) {
@@ -0,0 +1,97 @@
+//! Functions shared by the data format modules, whose values are resources of the format's own type
+
+use crate::prelude::*;
+
+/// A data format such as JSON, whose values convert to and from leas values
+pub(crate) trait Format: fmt::Debug + Sized + 'static {
+    /// Name of the module, also used for its values in messages
+    const NAME: &'static str;
+
+    /// Convert to a leas value, creating maps under `under`
+    fn to_value(&self, under: &Map) -> Result<Value>;
+
+    /// Convert from a leas value, finding the types written as strings in `env`
+    fn from_value(value: &Value, env: &Env) -> Result<Self>;
+
+    fn to_str(&self, line: usize) -> Result<String>;
+
+    fn from_str(s: &str, line: usize) -> Result<Self>;
+}
+
+/// Convert a value of the format to a leas value
+pub(crate) fn unwrap<F: Format>(map: &mut Map, arg: Value) -> Result<Value> {
+    let mut matcher = Matcher::single(F::NAME);
+    matcher.mat_or_err(arg, map.line())?;
+
+    let value = matcher.to_single().unwrap();
+
+    let line = map.line();
+    value.visit_res_or_else(
+        |v: &F| v.to_value(map),
+        move || {
+            Error::new(
+                format!(
+                    "Non-{} value cannot be converted using this function",
+                    F::NAME
+                ),
+                line,
+            )
+        },
+    )?
+}
+
+/// Convert a leas value to a value of the format
+pub(crate) fn from<F: Format>(map: &mut Map, arg: Value) -> Result<Value> {
+    let mut matcher = Matcher::single("value");
+    matcher.mat_or_err(arg, map.line())?;
+
+    let value = matcher.to_single().unwrap();
+
+    Ok(Value::Res(Resource::new(F::from_value(&value, map.env())?)))
+}
+
+/// Write a value of the format as a string
+pub(crate) fn to_str<F: Format>(map: &mut Map, arg: Value) -> Result<Value> {
+    let mut matcher = Matcher::single(F::NAME);
+    matcher.mat_or_err(arg, map.line())?;
+
+    let value = matcher.to_single().unwrap();
+
+    let line = map.line();
+    value.visit_res_or_else(
+        |v: &F| v.to_str(line).map(|s| s.into_value(map)),
+        move || {
+            Error::new(
+                format!("Non-{} value cannot be converted to string", F::NAME),
+                line,
+            )
+        },
+    )?
+}
+
+/// Read a value of the format from a string
+pub(crate) fn from_str<F: Format>(map: &mut Map, arg: Value) -> Result<Value> {
+    let mut matcher = Matcher::single("str");
+    matcher.mat_or_err(arg, map.line())?;
+
+    let s = matcher.to_single().unwrap();
+
+    let line = map.line();
+    s.visit_res_or_else(
+        move |s: &String| Ok(Value::Res(Resource::new(F::from_str(s, line)?))),
+        move || {
+            Error::new(
+                format!("Non-string value cannot be converted to {} value", F::NAME),
+                line,
+            )
+        },
+    )?
+}
+
+/// Register the functions every format has
+pub(crate) fn register<F: Format>(map: &mut Map) {
+    map.register("unwrap", unwrap::<F>);
+    map.register("from", from::<F>);
+    map.register("to_str", to_str::<F>);
+    map.register("from_str", from_str::<F>);
+}
@@ -1,126 +1,216 @@
+use super::num::{radix_of, to_radix, zero_pad};
 use crate::prelude::*;
 
-fn add(map: &mut Map, arg: Value) -> Result<Value> {
-    let mut matcher = Matcher::listed().with_singles(&["a", "b"]);
-    matcher.mat_or_err(arg, map.line())?;
-    let mut list = matcher.to_values().unwrap();
+/// Error for an operation which has no result in range
+fn error(a: Int, op: &str, b: Int) -> Error {
+    if b == 0 && (op == "/" || op == "%") {
+        Error::plain(format!("Division by zero when computing {} {} 0", a, op))
+    } else {
+        Error::plain(format!(
+            "Integer overflow when computing {} {} {}",
+            a, op, b
+        ))
+    }
+}
+
+/// Wrapping and saturating operations still fail when dividing by zero
+fn zero_divisor(a: Int, op: &str, b: Int) -> Result<()> {
+    if b == 0 && (op == "/" || op == "%") {
+        Err(error(a, op, b))
+    } else {
+        Ok(())
+    }
+}
 
-    let a = list.pop_front().unwrap();
-    let b = list.pop_front().unwrap();
+macro_rules! checked {
+    ($($name: ident, $checked: ident => $op: literal;)*) => {$(
+        fn $name(a: Int, b: Int) -> Result<Int> {
+            a.$checked(b).ok_or_else(|| error(a, $op, b))
+        }
 
-    a.as_int()
-        .and_then(|a| b.as_int().map(|b| a + b))
-        .map(Value::Int)
-        .ok_or_else(move || Error::new("Arguments a and b are not integers", map.line()))
+        fn $checked(a: Int, b: Int) -> Result<Option<Int>> {
+            Ok(a.$checked(b))
+        }
+    )*};
 }
 
-fn sub(map: &mut Map, arg: Value) -> Result<Value> {
-    let mut matcher = Matcher::listed().with_singles(&["a", "b"]);
-    matcher.mat_or_err(arg, map.line())?;
-    let mut list = matcher.to_values().unwrap();
+checked! {
+    add, checked_add => "+";
+    sub, checked_sub => "-";
+    mul, checked_mul => "*";
+    div, checked_div => "/";
+    rem, checked_rem => "%";
+}
 
-    let a = list.pop_front().unwrap();
-    let b = list.pop_front().unwrap();
+macro_rules! wrapping {
+    ($($name: ident => $op: literal;)*) => {$(
+        fn $name(a: Int, b: Int) -> Result<Int> {
+            zero_divisor(a, $op, b)?;
+            Ok(a.$name(b))
+        }
+    )*};
+}
 
-    a.as_int()
-        .and_then(|a| b.as_int().map(|b| a - b))
-        .map(Value::Int)
-        .ok_or_else(move || Error::new("Arguments a and b are not integers", map.line()))
+wrapping! {
+    wrapping_add => "+";
+    wrapping_sub => "-";
+    wrapping_mul => "*";
+    wrapping_div => "/";
+    wrapping_rem => "%";
+    saturating_add => "+";
+    saturating_sub => "-";
+    saturating_mul => "*";
+    saturating_div => "/";
 }
 
-fn mul(map: &mut Map, arg: Value) -> Result<Value> {
-    let mut matcher = Matcher::listed().with_singles(&["a", "b"]);
-    matcher.mat_or_err(arg, map.line())?;
-    let mut list = matcher.to_values().unwrap();
+fn pow(a: Int, b: Uint) -> Result<Int> {
+    u32::try_from(b)
+        .ok()
+        .and_then(|b| a.checked_pow(b))
+        .ok_or_else(|| {
+            Error::plain(format!(
+                "Integer overflow when computing {} to the power of {}",
+                a, b
+            ))
+        })
+}
 
-    let a = list.pop_front().unwrap();
-    let b = list.pop_front().unwrap();
+fn abs(n: Int) -> Result<Int> {
+    n.checked_abs()
+        .ok_or_else(|| Error::plain(format!("Integer overflow when computing abs of {}", n)))
+}
 
-    a.as_int()
-        .and_then(|a| b.as_int().map(|b| a * b))
-        .map(Value::Int)
-        .ok_or_else(move || Error::new("Arguments a and b are not integers", map.line()))
+fn gcd(a: Int, b: Int) -> Result<Int> {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a.try_into()
+        .map_err(|_| Error::plain(format!("Integer overflow when computing gcd {}", a)))
 }
 
-fn div(map: &mut Map, arg: Value) -> Result<Value> {
-    let mut matcher = Matcher::listed().with_singles(&["a", "b"]);
-    matcher.mat_or_err(arg, map.line())?;
-    let mut list = matcher.to_values().unwrap();
+fn min(a: Int, b: Int) -> Result<Int> {
+    Ok(a.min(b))
+}
 
-    let a = list.pop_front().unwrap();
-    let b = list.pop_front().unwrap();
+fn max(a: Int, b: Int) -> Result<Int> {
+    Ok(a.max(b))
+}
 
-    a.as_int()
-        .and_then(|a| b.as_int().map(|b| a / b))
-        .map(Value::Int)
-        .ok_or_else(move || Error::new("Arguments a and b are not integers", map.line()))
+fn and(a: Int, b: Int) -> Result<Int> {
+    Ok(a & b)
 }
 
-fn rem(map: &mut Map, arg: Value) -> Result<Value> {
-    let mut matcher = Matcher::listed().with_singles(&["a", "b"]);
-    matcher.mat_or_err(arg, map.line())?;
-    let mut list = matcher.to_values().unwrap();
+fn or(a: Int, b: Int) -> Result<Int> {
+    Ok(a | b)
+}
 
-    let a = list.pop_front().unwrap();
-    let b = list.pop_front().unwrap();
+fn xor(a: Int, b: Int) -> Result<Int> {
+    Ok(a ^ b)
+}
 
-    a.as_int()
-        .and_then(|a| b.as_int().map(|b| a % b))
-        .map(Value::Int)
-        .ok_or_else(move || Error::new("Arguments a and b are not integers", map.line()))
+fn not(n: Int) -> Result<Int> {
+    Ok(!n)
 }
 
-fn eq(map: &mut Map, arg: Value) -> Result<Value> {
-    let mut matcher = Matcher::listed().with_singles(&["a", "b"]);
-    matcher.mat_or_err(arg, map.line())?;
-    let mut list = matcher.to_values().unwrap();
+macro_rules! shift {
+    ($($name: ident, $checked: ident => $direction: literal;)*) => {$(
+        fn $name(n: Int, by: Uint) -> Result<Int> {
+            u32::try_from(by)
+                .ok()
+                .and_then(|by| n.$checked(by))
+                .ok_or_else(|| Error::plain(format!("Cannot shift {} {} by {} bits", n, $direction, by)))
+        }
+    )*};
+}
+
+shift! {
+    shl, checked_shl => "left";
+    shr, checked_shr => "right";
+}
+
+fn eq(a: Int, b: Int) -> Result<Bool> {
+    Ok(a == b)
+}
 
-    let a = list.pop_front().unwrap();
-    let b = list.pop_front().unwrap();
+fn lt(a: Int, b: Int) -> Result<Bool> {
+    Ok(a < b)
+}
 
-    a.as_int()
-        .and_then(|a| b.as_int().map(|b| a == b))
-        .map(Value::Bool)
-        .ok_or_else(move || Error::new("Arguments a and b are not integers", map.line()))
+fn to_float(n: Int) -> Result<Float> {
+    Ok(n as Float)
 }
 
-fn lt(map: &mut Map, arg: Value) -> Result<Value> {
-    let mut matcher = Matcher::listed().with_singles(&["a", "b"]);
-    matcher.mat_or_err(arg, map.line())?;
-    let mut list = matcher.to_values().unwrap();
+fn to_uint(n: Int) -> Result<Uint> {
+    n.try_into()
+        .map_err(|_| Error::plain(format!("Integer {} is out of range of uint", n)))
+}
 
-    let a = list.pop_front().unwrap();
-    let b = list.pop_front().unwrap();
+fn parse(s: String) -> Result<Option<Int>> {
+    Ok(s.trim().parse().ok())
+}
+
+fn parse_radix(s: String, radix: Uint) -> Result<Option<Int>> {
+    Ok(Int::from_str_radix(s.trim(), radix_of(radix)?).ok())
+}
 
-    a.as_int()
-        .and_then(|a| b.as_int().map(|b| a < b))
-        .map(Value::Bool)
-        .ok_or_else(move || Error::new("Arguments a and b are not integers", map.line()))
+fn to_str(n: Int) -> Result<String> {
+    Ok(n.to_string())
 }
 
-fn to_float(map: &mut Map, arg: Value) -> Result<Value> {
-    arg.as_int()
-        .map(|n| Value::Float(n as Float))
-        .ok_or_else(|| Error::new("Argument is not an integer", map.line()))
+fn to_str_radix(n: Int, radix: Uint) -> Result<String> {
+    let digits = to_radix(n.unsigned_abs(), radix_of(radix)?);
+    Ok(if n < 0 {
+        format!("-{}", digits)
+    } else {
+        digits
+    })
 }
 
-fn to_uint(map: &mut Map, arg: Value) -> Result<Value> {
-    arg.as_int()
-        .and_then(|n| n.try_into().ok())
-        .map(Value::Uint)
-        .ok_or_else(|| Error::new("Argument is not an integer", map.line()))
+fn to_str_padded(n: Int, width: Uint) -> Result<String> {
+    zero_pad(n.to_string(), width)
 }
 
 fn init_module(map: &mut Map) {
-    map.register("add", add);
-    map.register("sub", sub);
-    map.register("mul", mul);
-    map.register("div", div);
-    map.register("mod", rem);
-    map.register("eq", eq);
-    map.register("lt", lt);
-    map.register("to_float", to_float);
-    map.register("to_uint", to_uint);
+    map.register_fn("add", add);
+    map.register_fn("sub", sub);
+    map.register_fn("mul", mul);
+    map.register_fn("div", div);
+    map.register_fn("mod", rem);
+    map.register_fn("checked_add", checked_add);
+    map.register_fn("checked_sub", checked_sub);
+    map.register_fn("checked_mul", checked_mul);
+    map.register_fn("checked_div", checked_div);
+    map.register_fn("checked_mod", checked_rem);
+    map.register_fn("wrapping_add", wrapping_add);
+    map.register_fn("wrapping_sub", wrapping_sub);
+    map.register_fn("wrapping_mul", wrapping_mul);
+    map.register_fn("wrapping_div", wrapping_div);
+    map.register_fn("wrapping_mod", wrapping_rem);
+    map.register_fn("saturating_add", saturating_add);
+    map.register_fn("saturating_sub", saturating_sub);
+    map.register_fn("saturating_mul", saturating_mul);
+    map.register_fn("saturating_div", saturating_div);
+    map.register_fn("pow", pow);
+    map.register_fn("abs", abs);
+    map.register_fn("gcd", gcd);
+    map.register_fn("min", min);
+    map.register_fn("max", max);
+    map.register_fn("and", and);
+    map.register_fn("or", or);
+    map.register_fn("xor", xor);
+    map.register_fn("not", not);
+    map.register_fn("shl", shl);
+    map.register_fn("shr", shr);
+    map.register_fn("eq", eq);
+    map.register_fn("lt", lt);
+    map.register_fn("to_float", to_float);
+    map.register_fn("to_uint", to_uint);
+    map.register_fn("parse", parse);
+    map.register_fn("parse_radix", parse_radix);
+    map.register_fn("to_str", to_str);
+    map.register_fn("to_str_radix", to_str_radix);
+    map.register_fn("to_str_padded", to_str_padded);
 }
 
 pub fn init(map: &mut Map) {
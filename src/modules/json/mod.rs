@@ -0,0 +1,66 @@
+//! JSON values for leas, converted through the serde support in `sys`.
+//!
+//! `int`, `uint` and `float` are written as JSON numbers, where non-finite floats become `null`.
+//! Both `null` and `stop` are written as `null`, except that `stop` entries of maps are skipped.
+//! Map keys are written in the order of the map, and kept in source order when reading.
+//! When reading, integers become `int` unless they only fit in `uint`.
+
+use serde::de::DeserializeSeed;
+
+use crate::modules::format::{self, Format};
+use crate::prelude::*;
+
+impl Format for serde_json::Value {
+    const NAME: &'static str = "json";
+
+    fn to_value(&self, under: &Map) -> Result<Value> {
+        ValueSeed::new(under)
+            .deserialize(self)
+            .map_err(|err| Error::with_source(err, "When converting from json value"))
+    }
+
+    fn from_value(value: &Value, env: &Env) -> Result<Self> {
+        serde_json::to_value(sys::serializable(value, env))
+            .map_err(|err| Error::with_source(err, "When converting to json value"))
+    }
+
+    fn to_str(&self, _line: usize) -> Result<String> {
+        serde_json::to_string(self)
+            .map_err(|err| Error::with_source(err, "When converting to json string"))
+    }
+
+    fn from_str(s: &str, _line: usize) -> Result<Self> {
+        serde_json::from_str(s)
+            .map_err(|err| Error::with_source(err, "When converting string to json value"))
+    }
+}
+
+fn to_str_pretty(map: &mut Map, arg: Value) -> Result<Value> {
+    let mut matcher = Matcher::single("json");
+    matcher.mat_or_err(arg, map.line())?;
+
+    let json_value = matcher.to_single().unwrap();
+
+    let line = map.line();
+    let value = json_value.visit_res_or_else(
+        |v: &serde_json::Value| {
+            Ok(Value::Res(Resource::new(
+                serde_json::to_string_pretty(v).map_err(|err| {
+                    Error::with_source(err, "When converting to pretty json string")
+                })?,
+            )))
+        },
+        move || Error::new("Non-json value cannot be converted to string", line),
+    )??;
+
+    Ok(value)
+}
+
+fn init_module(map: &mut Map) {
+    format::register::<serde_json::Value>(map);
+    map.register("to_str_pretty", to_str_pretty);
+}
+
+pub fn init(map: &mut Map) {
+    map.register_init("json", init_module);
+}
@@ -1,9 +1,30 @@
+#[cfg(feature = "bigint")]
+pub mod bigint;
 #[cfg(feature = "bool")]
 pub mod bool;
+#[cfg(feature = "char")]
+pub mod char;
+#[cfg(feature = "dict")]
+pub mod dict;
 #[cfg(feature = "float")]
 pub mod float;
+#[cfg(any(feature = "json", feature = "toml_c"))]
+mod format;
 #[cfg(feature = "int")]
 pub mod int;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(any(
+    feature = "bigint",
+    feature = "char",
+    feature = "float",
+    feature = "int",
+    feature = "str",
+    feature = "uint"
+))]
+mod num;
+#[cfg(feature = "regex")]
+pub mod regex;
 #[cfg(feature = "str")]
 pub mod str;
 #[cfg(feature = "sys")]
@@ -14,3 +35,60 @@ pub mod toml;
 pub mod uint;
 #[cfg(feature = "vec")]
 pub mod vec;
+
+use crate::sys::Map;
+
+type Init = fn(&mut Map);
+
+/// Every module compiled into this build, with the function registering its initializer
+const MODULES: &[(&str, Init)] = &[
+    #[cfg(feature = "bigint")]
+    ("bigint", bigint::init),
+    #[cfg(feature = "bool")]
+    ("bool", bool::init),
+    #[cfg(feature = "char")]
+    ("char", char::init),
+    #[cfg(feature = "dict")]
+    ("dict", dict::init),
+    #[cfg(feature = "float")]
+    ("float", float::init),
+    #[cfg(feature = "int")]
+    ("int", int::init),
+    #[cfg(feature = "json")]
+    ("json", json::init),
+    #[cfg(feature = "regex")]
+    ("regex", regex::init),
+    #[cfg(feature = "str")]
+    ("str", str::init),
+    #[cfg(feature = "sys")]
+    ("sys", sys::init),
+    #[cfg(feature = "toml_c")]
+    ("toml", toml::init),
+    #[cfg(feature = "uint")]
+    ("uint", uint::init),
+    #[cfg(feature = "vec")]
+    ("vec", vec::init),
+];
+
+/// Names of all modules compiled into this build
+pub fn names() -> impl Iterator<Item = &'static str> {
+    MODULES.iter().map(|(name, _)| *name)
+}
+
+/// Register the module with the given name, returning `false` if no such module is compiled in
+pub fn init(map: &mut Map, name: &str) -> bool {
+    match MODULES.iter().find(|(module, _)| *module == name) {
+        Some((_, init)) => {
+            init(map);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Register all modules compiled into this build
+pub fn init_all(map: &mut Map) {
+    for (_, init) in MODULES {
+        init(map);
+    }
+}
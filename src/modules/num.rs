@@ -0,0 +1,55 @@
+//! Helpers for parsing and formatting numbers shared by the number modules
+
+// Builds with only some of the number modules do not use every helper
+#![allow(dead_code)]
+
+use crate::prelude::*;
+
+/// Check a radix given by a script
+pub(crate) fn radix_of(radix: Uint) -> Result<u32> {
+    match radix {
+        2..=36 => Ok(radix as u32),
+        _ => Err(Error::plain(format!(
+            "Radix should be between 2 and 36, found {}",
+            radix
+        ))),
+    }
+}
+
+/// Largest width or precision accepted when formatting, the most `format!` supports
+pub(crate) const MAX_FORMAT_LEN: Uint = u16::MAX as Uint;
+
+/// Check a width or precision given by a script, `what` naming it in the error
+pub(crate) fn format_len(n: Uint, what: &str) -> Result<usize> {
+    if n <= MAX_FORMAT_LEN {
+        Ok(n as usize)
+    } else {
+        Err(Error::plain(format!(
+            "{} should be at most {}, found {}",
+            what, MAX_FORMAT_LEN, n
+        )))
+    }
+}
+
+/// Digits of `n` in the radix, using lowercase letters after 9
+pub(crate) fn to_radix(mut n: u64, radix: u32) -> String {
+    let mut digits = Vec::new();
+    loop {
+        digits.push(char::from_digit((n % radix as u64) as u32, radix).unwrap());
+        n /= radix as u64;
+        if n == 0 {
+            break;
+        }
+    }
+    digits.iter().rev().collect()
+}
+
+/// Pad the digits with zeros after the sign to reach the width
+pub(crate) fn zero_pad(s: String, width: Uint) -> Result<String> {
+    let (sign, digits) = match s.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", s.as_str()),
+    };
+    let width = format_len(width, "Width")?.saturating_sub(sign.len());
+    Ok(format!("{}{:0>width$}", sign, digits, width = width))
+}
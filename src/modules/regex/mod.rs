@@ -0,0 +1,172 @@
+//! Regular expressions
+//!
+//! Patterns are compiled once with `regex.new` into resources.
+//! Every function taking a pattern also accepts a string, compiled on each call.
+
+use crate::prelude::*;
+use regex::{Captures, Regex};
+
+fn regex_of(value: &Value, line: usize) -> Result<Regex> {
+    if let Some(regex) = value
+        .as_res()
+        .and_then(|res| res.visit(|re: &Regex| re.clone()))
+    {
+        return Ok(regex);
+    }
+    let pattern = value.extract::<String>(line).map_err(|_| {
+        Error::new(
+            format!("Expected regex or str as pattern, found {}", value),
+            line,
+        )
+    })?;
+    Regex::new(&pattern)
+        .map_err(|err| Error::with_source(err, format!("When compiling regex {:?}", pattern)))
+}
+
+/// Split the argument into the pattern and the string
+fn args_of(map: &Map, arg: Value) -> Result<(Regex, String)> {
+    let line = map.line();
+    let (re, s) = arg.extract::<(Value, String)>(line)?;
+    Ok((regex_of(&re, line)?, s))
+}
+
+/// Groups of a match, with stop for groups which did not participate
+fn groups_of(captures: &Captures, under: &Map) -> Value {
+    captures
+        .iter()
+        .map(|group| group.map(|group| group.as_str().to_string()))
+        .collect::<VecDeque<_>>()
+        .into_value(under)
+}
+
+fn new(map: &mut Map, arg: Value) -> Result<Value> {
+    let pattern = arg.extract::<String>(map.line())?;
+    let regex = Regex::new(&pattern)
+        .map_err(|err| Error::with_source(err, format!("When compiling regex {:?}", pattern)))?;
+    Ok(Value::Res(Resource::new(regex)))
+}
+
+fn is_match(map: &mut Map, arg: Value) -> Result<Value> {
+    let (re, s) = args_of(map, arg)?;
+    Ok(Value::Bool(re.is_match(&s)))
+}
+
+fn find(map: &mut Map, arg: Value) -> Result<Value> {
+    let (re, s) = args_of(map, arg)?;
+    Ok(re.find(&s).map(|found| found.as_str()).into_value(map))
+}
+
+fn find_all(map: &mut Map, arg: Value) -> Result<Value> {
+    let (re, s) = args_of(map, arg)?;
+    Ok(re
+        .find_iter(&s)
+        .map(|found| found.as_str())
+        .collect::<VecDeque<_>>()
+        .into_value(map))
+}
+
+fn captures(map: &mut Map, arg: Value) -> Result<Value> {
+    let (re, s) = args_of(map, arg)?;
+    Ok(re
+        .captures(&s)
+        .map(|captures| groups_of(&captures, map))
+        .unwrap_or(Value::Stop))
+}
+
+fn captures_all(map: &mut Map, arg: Value) -> Result<Value> {
+    let (re, s) = args_of(map, arg)?;
+    Ok(re
+        .captures_iter(&s)
+        .map(|captures| groups_of(&captures, map))
+        .collect::<VecDeque<_>>()
+        .into_value(map))
+}
+
+/// Named groups of a match as a dict, with stop for groups which did not participate
+fn named_of(re: &Regex, captures: &Captures, under: &Map) -> Value {
+    let mut result = Map::new_under(under);
+    for name in re.capture_names().flatten() {
+        let group = captures.name(name).map(|group| group.as_str());
+        result.forced_set(name.to_string(), group.into_value(under));
+    }
+    Value::Res(Resource::new(result))
+}
+
+fn named_captures(map: &mut Map, arg: Value) -> Result<Value> {
+    let (re, s) = args_of(map, arg)?;
+    Ok(re
+        .captures(&s)
+        .map(|captures| named_of(&re, &captures, map))
+        .unwrap_or(Value::Stop))
+}
+
+/// Replace up to `limit` matches, 0 for all of them.
+/// The replacement is a string with `$1` or `$name` references,
+/// or a function called with the groups and the dict of named groups
+fn replace_with(map: &mut Map, arg: Value, limit: usize) -> Result<Value> {
+    let line = map.line();
+    let (re, s, replacement) = arg.extract::<(Value, String, Value)>(line)?;
+    let re = regex_of(&re, line)?;
+
+    if let Some(replacement) = String::from_value(&replacement) {
+        return Ok(re.replacen(&s, limit, replacement.as_str()).into_value(map));
+    }
+
+    let mut result = String::new();
+    let mut last = 0;
+    for (index, captures) in re.captures_iter(&s).enumerate() {
+        if limit != 0 && index >= limit {
+            break;
+        }
+        let whole = captures.get(0).unwrap();
+        result.push_str(&s[last..whole.start()]);
+        let groups = (groups_of(&captures, map), named_of(&re, &captures, map)).into_value(map);
+        let value = replacement.call(map, groups).ok_or_else(|| {
+            Error::new(
+                format!("Expected str or fn as replacement, found {}", replacement),
+                line,
+            )
+        })??;
+        result.push_str(&value.extract::<String>(line)?);
+        last = whole.end();
+    }
+    result.push_str(&s[last..]);
+
+    Ok(result.into_value(map))
+}
+
+fn replace(map: &mut Map, arg: Value) -> Result<Value> {
+    replace_with(map, arg, 1)
+}
+
+fn replace_all(map: &mut Map, arg: Value) -> Result<Value> {
+    replace_with(map, arg, 0)
+}
+
+fn split(map: &mut Map, arg: Value) -> Result<Value> {
+    let (re, s) = args_of(map, arg)?;
+    Ok(re.split(&s).collect::<VecDeque<_>>().into_value(map))
+}
+
+fn init_module(map: &mut Map) {
+    map.register("new", new);
+    map.register("is_match", is_match);
+    map.register("find", find);
+    map.register("find_all", find_all);
+    map.register("captures", captures);
+    map.register("captures_all", captures_all);
+    map.register("named_captures", named_captures);
+    map.register("replace", replace);
+    map.register("replace_all", replace_all);
+    map.register("split", split);
+    map.register_class(
+        Class::builder::<Regex>("regex")
+            .getter("pattern", |re| re.as_str().to_string())
+            .method("to_str", |re, map, _| Ok(re.as_str().into_value(map)))
+            .build(),
+    );
+}
+
+pub fn init(map: &mut Map) {
+    map.register_init("regex", init_module);
+}
@@ -0,0 +1,134 @@
+//! Character indexes of strings, so that indexing by code point does not walk the string.
+//!
+//! Indexes are cached with the string resource and dropped whenever it is visited mutably,
+//! except by `push`, `pop` and `set`, which keep the index up to date through `visit_mut`.
+
+use crate::prelude::*;
+
+/// Characters between two recorded offsets, strings shorter than this are not cached
+const STRIDE: usize = 64;
+
+/// Where the characters of a string start
+pub(super) struct CharIndex {
+    /// Byte length of the string
+    len: usize,
+    chars: usize,
+    /// Byte offset of every `STRIDE`th character
+    offsets: Vec<usize>,
+}
+
+impl CharIndex {
+    fn new(s: &str) -> Self {
+        let mut index = Self {
+            len: 0,
+            chars: 0,
+            offsets: Vec::new(),
+        };
+        index.push(s);
+        index
+    }
+
+    pub(super) fn push(&mut self, s: &str) {
+        if s.is_ascii() {
+            let first = self.chars.next_multiple_of(STRIDE);
+            let end = self.chars + s.len();
+            self.offsets.extend(
+                (first..end)
+                    .step_by(STRIDE)
+                    .map(|c| c - self.chars + self.len),
+            );
+            self.chars = end;
+        } else {
+            for (offset, _) in s.char_indices() {
+                if self.chars.is_multiple_of(STRIDE) {
+                    self.offsets.push(self.len + offset);
+                }
+                self.chars += 1;
+            }
+        }
+        self.len += s.len();
+    }
+
+    pub(super) fn pop(&mut self, c: char) {
+        self.chars -= 1;
+        self.len -= c.len_utf8();
+        if self.chars.is_multiple_of(STRIDE) {
+            self.offsets.pop();
+        }
+    }
+
+    /// Strings of ASCII characters only have one byte per character
+    fn is_ascii(&self) -> bool {
+        self.chars == self.len
+    }
+
+    pub(super) fn chars(&self) -> usize {
+        self.chars
+    }
+
+    /// Byte offset of the character at `index`, which may be one past the last character
+    pub(super) fn byte_of(&self, s: &str, index: usize) -> Option<usize> {
+        if index > self.chars {
+            return None;
+        }
+        if self.is_ascii() {
+            return Some(index);
+        }
+        let start = self
+            .offsets
+            .get(index / STRIDE)
+            .copied()
+            .unwrap_or(self.len);
+        s.get(start..)?
+            .char_indices()
+            .map(|(offset, _)| start + offset)
+            .chain(std::iter::once(self.len))
+            .nth(index % STRIDE)
+    }
+
+    /// Byte range of the character at `index`
+    pub(super) fn char_range(&self, s: &str, index: usize) -> Option<(usize, usize)> {
+        if index >= self.chars {
+            return None;
+        }
+        let start = self.byte_of(s, index)?;
+        let c = s.get(start..)?.chars().next()?;
+        Some((start, start + c.len_utf8()))
+    }
+
+    /// Character index of the byte offset, which should be at the start of a character
+    pub(super) fn char_of(&self, s: &str, offset: usize) -> usize {
+        if self.is_ascii() {
+            return offset;
+        }
+        let block = self.offsets.partition_point(|&start| start <= offset) - 1;
+        let skipped = s.get(self.offsets[block]..offset).unwrap_or_default();
+        block * STRIDE + skipped.chars().count()
+    }
+}
+
+/// Run `f` with the index of the string held by `res`, indexing it if needed
+pub(super) fn with<R>(res: &Resource, s: &str, f: impl FnOnce(&CharIndex) -> R) -> R {
+    if s.len() < STRIDE {
+        return f(&CharIndex::new(s));
+    }
+    res.with_cache(|| CharIndex::new(s), f)
+}
+
+/// Change the string held by `res` with `f`, which keeps the index up to date or drops it
+pub(super) fn visit_mut<R: 'static>(
+    res: &Resource,
+    f: impl FnOnce(&mut String, &mut Option<CharIndex>) -> R,
+) -> Option<R> {
+    let mut index = res.take_cache::<CharIndex>();
+    let result = res.visit_mut(|s: &mut String| f(s, &mut index))?;
+    if let Some(index) = index {
+        res.set_cache(index);
+    }
+    Some(result)
+}
+
+/// The index of `s` held in `index`, indexing it if needed
+pub(super) fn get_or_insert<'a>(s: &str, index: &'a mut Option<CharIndex>) -> &'a CharIndex {
+    index.get_or_insert_with(|| CharIndex::new(s))
+}
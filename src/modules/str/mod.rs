@@ -1,4 +1,13 @@
+//! Strings
+//!
+//! Every index and length is counted in Unicode code points, except `byte_length`.
+//! Characters are passed around as uints holding the code point.
+
+mod index;
+
+use super::num;
 use crate::prelude::*;
+use index::CharIndex;
 
 fn empty(_: &mut Map, _: Value) -> Result<Value> {
     Ok(Value::Res(Resource::new(String::new())))
@@ -17,12 +26,15 @@ fn push(map: &mut Map, arg: Value) -> Result<Value> {
         |arg: &String| arg.clone(),
         move || Error::new("Argument arg is not a string", line),
     )?;
-    s.visit_mut_res_or_else(
-        move |s: &mut String| {
-            s.push_str(&str);
-        },
-        move || Error::new("Argument str is not a string", line),
-    )?;
+    let not_str = move || Error::new("Argument str is not a string", line);
+    let res = s.as_res().ok_or_else(not_str)?;
+    index::visit_mut(&res, |s, index| {
+        s.push_str(&str);
+        if let Some(index) = index {
+            index.push(&str);
+        }
+    })
+    .ok_or_else(not_str)?;
 
     Ok(Value::Null)
 }
@@ -34,14 +46,18 @@ fn pop(map: &mut Map, arg: Value) -> Result<Value> {
     let s = matcher.to_single().unwrap();
 
     let line = map.line();
-    let result = s
-        .visit_mut_res_or_else(
-            |s: &mut String| s.pop().map(|c| Value::Uint(c as Uint)),
-            move || Error::new("Argument str is not a string", line),
-        )?
-        .unwrap_or(Value::Stop);
-
-    Ok(result)
+    let not_str = move || Error::new("Argument str is not a string", line);
+    let res = s.as_res().ok_or_else(not_str)?;
+    let popped = index::visit_mut(&res, |s, index| {
+        let c = s.pop()?;
+        if let Some(index) = index {
+            index.pop(c);
+        }
+        Some(c)
+    })
+    .ok_or_else(not_str)?;
+
+    Ok(popped.map_or(Value::Stop, |c| Value::Uint(c as Uint)))
 }
 
 fn get(map: &mut Map, arg: Value) -> Result<Value> {
@@ -52,17 +68,13 @@ fn get(map: &mut Map, arg: Value) -> Result<Value> {
     let s = list.pop_front().unwrap();
     let i = list.pop_front().unwrap();
 
-    let line = map.line();
-    let result = s
-        .visit_res_or_else(
-            |s: &String| {
-                let i = i.as_uint()?;
-                let c = s.chars().nth(i as usize)?;
-                Some(Value::Uint(c as Uint))
-            },
-            move || Error::new("Argument str is not a string", line),
-        )?
-        .unwrap_or(Value::Stop);
+    let result = with_index(&s, |s, index| {
+        let (start, _) = index.char_range(s, usize::try_from(i.as_uint()?).ok()?)?;
+        let c = s[start..].chars().next()?;
+        Some(Value::Uint(c as Uint))
+    })
+    .map_err(|err| err.or_line(map.line()))?
+    .unwrap_or(Value::Stop);
 
     Ok(result)
 }
@@ -77,17 +89,19 @@ fn set(map: &mut Map, arg: Value) -> Result<Value> {
     let c = list.pop_front().unwrap();
 
     let line = map.line();
-    s.visit_mut_res_or_else(
-        move |s: &mut String| {
-            let i = i.as_uint()?;
-            let c = char::from_u32(c.as_uint()? as u32)?;
-            let mut chars = s.chars().collect::<Vec<_>>();
-            chars[i as usize] = c;
-            *s = chars.into_iter().collect();
-            Some(())
-        },
-        move || Error::new("Argument str is not a string", line),
-    )?
+    let not_str = move || Error::new("Argument str is not a string", line);
+    let res = s.as_res().ok_or_else(not_str)?;
+    index::visit_mut(&res, |s, index| {
+        let i = usize::try_from(i.as_uint()?).ok()?;
+        let (start, end) = index::get_or_insert(s, index).char_range(s, i)?;
+        let c = char::from_u32(c.as_uint()?.try_into().ok()?)?;
+        s.replace_range(start..end, c.encode_utf8(&mut [0; 4]));
+        if c.len_utf8() != end - start {
+            *index = None;
+        }
+        Some(())
+    })
+    .ok_or_else(not_str)?
     .map_or_else(|| Ok(Value::Stop), |_| Ok(Value::Null))
 }
 
@@ -114,15 +128,13 @@ fn length(map: &mut Map, arg: Value) -> Result<Value> {
 
     let s = matcher.to_single().unwrap();
 
-    let line = map.line();
-    let result = s
-        .visit_res_or_else(
-            |s: &String| Some(Value::Uint(s.len() as Uint)),
-            move || Error::new("Argument str is not a string", line),
-        )?
-        .unwrap_or(Value::Stop);
+    let chars = with_index(&s, |_, index| index.chars()).map_err(|err| err.or_line(map.line()))?;
 
-    Ok(result)
+    Ok(Value::Uint(chars as Uint))
+}
+
+fn byte_length(s: Value) -> Result<Uint> {
+    with_str(&s, |s| s.len() as Uint)
 }
 
 fn to_chars(map: &mut Map, arg: Value) -> Result<Value> {
@@ -179,20 +191,25 @@ fn from_char(map: &mut Map, arg: Value) -> Result<Value> {
     let c = matcher.to_single().unwrap();
 
     let line = map.line();
-    let result = c
-        .visit_res_or_else(
-            |c: &Uint| {
-                let c = char::from_u32(*c as u32)?;
-                Some(Value::Res(Resource::new(c.to_string())))
-            },
-            move || Error::new("Argument uint is not a uint", line),
-        )?
+    let c = c
+        .as_uint()
+        .ok_or_else(|| Error::new("Argument uint is not a uint", line))?;
+    let result = u32::try_from(c)
+        .ok()
+        .and_then(char::from_u32)
+        .map(|c| Value::Res(Resource::new(c.to_string())))
         .unwrap_or(Value::Stop);
 
     Ok(result)
 }
 
-fn print(map: &mut Map, arg: Value) -> Result<Value> {
+/// Write the string to `sink` of the environment, returning the module for chaining
+fn write_to(
+    map: &mut Map,
+    arg: Value,
+    sink: fn(&Env) -> RefMut<'_, Box<dyn Write>>,
+    context: &str,
+) -> Result<Value> {
     let mut matcher: Matcher = Matcher::single("str");
     matcher.mat_or_err(arg, map.line())?;
 
@@ -201,22 +218,415 @@ fn print(map: &mut Map, arg: Value) -> Result<Value> {
     let line = map.line();
     s.visit_res_or_else(
         |s: &String| {
-            print!("{}", s);
-            Some(Value::Null)
+            write!(sink(map.env()), "{}", s).map_err(|err| Error::with_source(err, context))
         },
         move || Error::new("Argument str is not a string", line),
-    )?;
+    )??;
 
     map.req("self")
 }
 
+fn print(map: &mut Map, arg: Value) -> Result<Value> {
+    write_to(map, arg, Env::stdout, "When printing")
+}
+
+fn eprint(map: &mut Map, arg: Value) -> Result<Value> {
+    write_to(map, arg, Env::stderr, "When printing to stderr")
+}
+
 fn from(map: &mut Map, arg: Value) -> Result<Value> {
     let mut matcher: Matcher = Matcher::single("str");
     matcher.mat_or_err(arg, map.line())?;
 
     let s = matcher.to_single().unwrap();
 
-    Ok(Value::Res(Resource::new(s.to_string())))
+    Ok(Value::Res(Resource::new(s.to_str(map)?)))
+}
+
+fn repr(map: &mut Map, arg: Value) -> Result<Value> {
+    let mut matcher: Matcher = Matcher::single("value");
+    matcher.mat_or_err(arg, map.line())?;
+
+    let value = matcher.to_single().unwrap();
+
+    Ok(Value::Res(Resource::new(value.repr(map)?)))
+}
+
+/// Run `f` on the string without copying it
+fn with_str<R: 'static>(s: &Value, f: impl FnOnce(&str) -> R) -> Result<R> {
+    s.visit_res_or_else(
+        |s: &String| f(s),
+        || Error::plain("Argument str is not a string"),
+    )
+}
+
+/// Run `f` on the string and the index of its characters, to index it by code point
+fn with_index<R: 'static>(s: &Value, f: impl FnOnce(&str, &CharIndex) -> R) -> Result<R> {
+    let not_str = || Error::plain("Argument str is not a string");
+    let res = s.as_res().ok_or_else(not_str)?;
+    res.visit(|s: &String| index::with(&res, s, |index| f(s, index)))
+        .ok_or_else(not_str)
+}
+
+fn split(s: Value, sep: String) -> Result<Vec<String>> {
+    with_str(&s, |s| {
+        if sep.is_empty() {
+            s.chars().map(String::from).collect()
+        } else {
+            s.split(sep.as_str()).map(String::from).collect()
+        }
+    })
+}
+
+fn join(vec: Vec<String>, sep: String) -> Result<String> {
+    Ok(vec.join(&sep))
+}
+
+fn find(s: Value, pat: String) -> Result<Option<Uint>> {
+    with_index(&s, |s, index| {
+        s.find(pat.as_str())
+            .map(|offset| index.char_of(s, offset) as Uint)
+    })
+}
+
+fn rfind(s: Value, pat: String) -> Result<Option<Uint>> {
+    with_index(&s, |s, index| {
+        s.rfind(pat.as_str())
+            .map(|offset| index.char_of(s, offset) as Uint)
+    })
+}
+
+/// Replace every occurrence of `from`, which cannot be empty
+fn replace(s: Value, from: String, to: String) -> Result<String> {
+    if from.is_empty() {
+        return Err(Error::plain("Cannot replace an empty string"));
+    }
+    with_str(&s, |s| {
+        let count = s.matches(from.as_str()).count();
+        (s.len() - count * from.len())
+            .checked_add(count.checked_mul(to.len())?)
+            .filter(|&total| total <= MAX_BUILT_LEN)
+            .map(|_| s.replace(from.as_str(), &to))
+    })?
+    .ok_or_else(|| {
+        Error::plain(format!(
+            "Cannot build a string of more than {} bytes by replacing",
+            MAX_BUILT_LEN
+        ))
+    })
+}
+
+fn trim(s: Value) -> Result<String> {
+    with_str(&s, |s| s.trim().to_string())
+}
+
+fn trim_start(s: Value) -> Result<String> {
+    with_str(&s, |s| s.trim_start().to_string())
+}
+
+fn trim_end(s: Value) -> Result<String> {
+    with_str(&s, |s| s.trim_end().to_string())
+}
+
+fn starts_with(s: Value, pat: String) -> Result<Bool> {
+    with_str(&s, |s| s.starts_with(pat.as_str()))
+}
+
+fn ends_with(s: Value, pat: String) -> Result<Bool> {
+    with_str(&s, |s| s.ends_with(pat.as_str()))
+}
+
+fn contains(s: Value, pat: String) -> Result<Bool> {
+    with_str(&s, |s| s.contains(pat.as_str()))
+}
+
+/// Characters from `start` to `end`, both clamped to the end of the string,
+/// or stop if `start` is after `end`
+fn slice(s: Value, start: Uint, end: Uint) -> Result<Option<String>> {
+    if start > end {
+        return Ok(None);
+    }
+    with_index(&s, |s, index| {
+        let clamp = |i: Uint| usize::try_from(i).map_or(index.chars(), |i| i.min(index.chars()));
+        let start = index.byte_of(s, clamp(start))?;
+        let end = index.byte_of(s, clamp(end))?;
+        s.get(start..end).map(String::from)
+    })
+}
+
+/// Up to `len` characters from `start`, clamped like `slice`
+fn substr(s: Value, start: Uint, len: Uint) -> Result<Option<String>> {
+    slice(s, start, start.saturating_add(len))
+}
+
+fn to_upper(s: Value) -> Result<String> {
+    with_str(&s, |s| s.to_uppercase())
+}
+
+fn to_lower(s: Value) -> Result<String> {
+    with_str(&s, |s| s.to_lowercase())
+}
+
+/// Largest string in bytes built by `times`, `replace`, `pad_left` and `pad_right`
+const MAX_BUILT_LEN: usize = 1 << 30;
+
+/// Length in bytes of `count` copies of `len` bytes, if it is within `MAX_BUILT_LEN`
+fn built_len(len: usize, count: Uint) -> Result<usize> {
+    usize::try_from(count)
+        .ok()
+        .and_then(|count| len.checked_mul(count))
+        .filter(|&total| total <= MAX_BUILT_LEN)
+        .ok_or_else(|| {
+            Error::plain(format!(
+                "Cannot build a string of {} times {} bytes, the limit is {} bytes",
+                count, len, MAX_BUILT_LEN
+            ))
+        })
+}
+
+/// The string repeated `n` times
+fn times(s: Value, n: Uint) -> Result<String> {
+    with_str(&s, |s| {
+        built_len(s.len(), n)?;
+        Ok(s.repeat(n as usize))
+    })?
+}
+
+fn lines(s: Value) -> Result<Vec<String>> {
+    with_str(&s, |s| s.lines().map(String::from).collect())
+}
+
+fn compare(a: Value, b: Value) -> Result<Int> {
+    let a = with_str(&a, |a| a.to_string())?;
+    with_str(&b, |b| a.as_str().cmp(b) as Int)
+}
+
+/// Pad to `width` characters with spaces, or with the character given as third argument
+fn pad(map: &mut Map, arg: Value, left: bool) -> Result<Value> {
+    let line = map.line();
+    let (s, width, fill) = match arg.to_list().map(|list| list.len()) {
+        Some(3) => arg.extract::<(Value, Uint, String)>(line)?,
+        _ => {
+            let (s, width) = arg.extract::<(Value, Uint)>(line)?;
+            (s, width, " ".to_string())
+        }
+    };
+    let mut fill = fill.chars();
+    let fill = match (fill.next(), fill.next()) {
+        (Some(c), None) => c,
+        _ => return Err(Error::new("Padding should be a single character", line)),
+    };
+    let padded = with_index(&s, move |s, index| {
+        let count = width.saturating_sub(index.chars() as Uint);
+        built_len(fill.len_utf8(), count)?;
+        let padding = std::iter::repeat_n(fill, count as usize).collect::<String>();
+        Ok(if left {
+            padding + s
+        } else {
+            s.to_string() + &padding
+        })
+    })
+    .and_then(|padded| padded)
+    .map_err(|err| err.or_line(line))?;
+    Ok(padded.into_value(map))
+}
+
+fn pad_left(map: &mut Map, arg: Value) -> Result<Value> {
+    pad(map, arg, true)
+}
+
+fn pad_right(map: &mut Map, arg: Value) -> Result<Value> {
+    pad(map, arg, false)
+}
+
+/// Specification of a `str.format` placeholder, `[[fill]align][0][width][.precision][type]`
+#[derive(Default)]
+struct Spec {
+    fill: Option<char>,
+    align: Option<char>,
+    zero: bool,
+    width: usize,
+    precision: Option<usize>,
+    kind: Option<char>,
+}
+
+impl Spec {
+    fn parse(spec: &str, line: usize) -> Result<Self> {
+        let invalid = || Error::new(format!("Invalid format specification {:?}", spec), line);
+        let mut result = Self::default();
+        let chars = spec.chars().collect::<Vec<_>>();
+        let mut pos = 0;
+        let is_align = |c: Option<&char>| matches!(c, Some('<' | '>' | '^'));
+        if is_align(chars.get(1)) {
+            result.fill = Some(chars[0]);
+            result.align = Some(chars[1]);
+            pos = 2;
+        } else if is_align(chars.first()) {
+            result.align = Some(chars[0]);
+            pos = 1;
+        }
+        if chars.get(pos) == Some(&'0') {
+            result.zero = true;
+            pos += 1;
+        }
+        let digits = |pos: &mut usize| {
+            let start = *pos;
+            while chars.get(*pos).is_some_and(char::is_ascii_digit) {
+                *pos += 1;
+            }
+            chars[start..*pos].iter().collect::<String>()
+        };
+        // Both are bounded so that rendering cannot build huge strings
+        let format_len = |digits: String, what| {
+            let n = digits.parse().map_err(|_| invalid())?;
+            num::format_len(n, what).map_err(|err| err.or_line(line))
+        };
+        let width = digits(&mut pos);
+        if !width.is_empty() {
+            result.width = format_len(width, "Format width")?;
+        }
+        if chars.get(pos) == Some(&'.') {
+            pos += 1;
+            result.precision = Some(format_len(digits(&mut pos), "Format precision")?);
+        }
+        if let Some(c @ ('s' | 'r' | 'e' | 'x' | 'b' | 'o')) = chars.get(pos) {
+            result.kind = Some(*c);
+            pos += 1;
+        }
+        if pos == chars.len() {
+            Ok(result)
+        } else {
+            Err(invalid())
+        }
+    }
+
+    fn render(&self, value: &Value, map: &mut Map) -> Result<String> {
+        let line = map.line();
+        let radix = |n: u64, kind| match kind {
+            'x' => format!("{:x}", n),
+            'b' => format!("{:b}", n),
+            _ => format!("{:o}", n),
+        };
+        let body = match (self.kind, value) {
+            (Some('r'), _) => value.repr(map)?,
+            (Some('e'), Value::Float(n)) => match self.precision {
+                Some(precision) => format!("{:.*e}", precision, n),
+                None => format!("{:e}", n),
+            },
+            (Some(kind @ ('x' | 'b' | 'o')), Value::Int(n)) => {
+                let digits = radix(n.unsigned_abs(), kind);
+                if *n < 0 {
+                    format!("-{}", digits)
+                } else {
+                    digits
+                }
+            }
+            (Some(kind @ ('x' | 'b' | 'o')), Value::Uint(n)) => radix(*n, kind),
+            (Some('e' | 'x' | 'b' | 'o'), _) => {
+                return Err(Error::new(
+                    format!(
+                        "Cannot format {:#} with type {:?}",
+                        value,
+                        self.kind.unwrap()
+                    ),
+                    line,
+                ))
+            }
+            (_, Value::Float(n)) if self.precision.is_some() => {
+                format!("{:.*}", self.precision.unwrap(), n)
+            }
+            _ => {
+                let str = value.to_str(map)?;
+                match self.precision {
+                    Some(precision)
+                        if value.target_type_of(map.env()).as_deref() == Some("str") =>
+                    {
+                        str.chars().take(precision).collect()
+                    }
+                    _ => str,
+                }
+            }
+        };
+
+        let len = body.chars().count();
+        if len >= self.width {
+            return Ok(body);
+        }
+        let count = self.width - len;
+        let is_number = matches!(value, Value::Int(_) | Value::Uint(_) | Value::Float(_));
+        if self.zero && self.align.is_none() && is_number {
+            let (sign, digits) = match body.strip_prefix('-') {
+                Some(digits) => ("-", digits),
+                None => ("", body.as_str()),
+            };
+            return Ok(format!("{}{}{}", sign, "0".repeat(count), digits));
+        }
+        let fill = self.fill.unwrap_or(' ').to_string();
+        let align = self.align.unwrap_or(if is_number { '>' } else { '<' });
+        Ok(match align {
+            '<' => body + &fill.repeat(count),
+            '>' => fill.repeat(count) + &body,
+            _ => fill.repeat(count / 2) + &body + &fill.repeat(count - count / 2),
+        })
+    }
+}
+
+/// Replace `{}`, `{index}` and `{index:spec}` in the format with the arguments,
+/// `{{` and `}}` stand for single braces.
+fn format(map: &mut Map, arg: Value) -> Result<Value> {
+    let line = map.line();
+    let mut args = match arg.to_list() {
+        Some(list) => list,
+        None => VecDeque::from([arg]),
+    };
+    let fmt = args
+        .pop_front()
+        .unwrap_or(Value::Null)
+        .extract::<String>(line)?;
+
+    let mut result = String::new();
+    let mut next = 0;
+    let mut chars = fmt.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                result.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                result.push('}');
+            }
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => placeholder.push(c),
+                        None => return Err(Error::new("Unclosed placeholder in format", line)),
+                    }
+                }
+                let (index, spec) = placeholder
+                    .split_once(':')
+                    .unwrap_or((placeholder.as_str(), ""));
+                let index = if index.is_empty() {
+                    next += 1;
+                    next - 1
+                } else {
+                    index.parse::<usize>().map_err(|_| {
+                        Error::new(format!("Invalid placeholder {{{}}}", placeholder), line)
+                    })?
+                };
+                let value = args.get(index).cloned().ok_or_else(|| {
+                    Error::new(format!("No argument {} for format {:?}", index, fmt), line)
+                })?;
+                result.push_str(&Spec::parse(spec, line)?.render(&value, map)?);
+            }
+            _ => result.push(c),
+        }
+    }
+
+    Ok(result.into_value(map))
 }
 
 fn init_module(map: &mut Map) {
@@ -227,11 +637,35 @@ fn init_module(map: &mut Map) {
     map.register("set", set);
     map.register("clone", clone);
     map.register("length", length);
+    map.register_fn("byte_length", byte_length);
     map.register("to_chars", to_chars);
     map.register("from_chars", from_chars);
     map.register("from_char", from_char);
     map.register("print", print);
+    map.register("eprint", eprint);
     map.register("from", from);
+    map.register("repr", repr);
+    map.register_fn("split", split);
+    map.register_fn("join", join);
+    map.register_fn("find", find);
+    map.register_fn("rfind", rfind);
+    map.register_fn("replace", replace);
+    map.register_fn("trim", trim);
+    map.register_fn("trim_start", trim_start);
+    map.register_fn("trim_end", trim_end);
+    map.register_fn("starts_with", starts_with);
+    map.register_fn("ends_with", ends_with);
+    map.register_fn("contains", contains);
+    map.register_fn("slice", slice);
+    map.register_fn("substr", substr);
+    map.register_fn("to_upper", to_upper);
+    map.register_fn("to_lower", to_lower);
+    map.register_fn("times", times);
+    map.register_fn("lines", lines);
+    map.register_fn("compare", compare);
+    map.register("pad_left", pad_left);
+    map.register("pad_right", pad_right);
+    map.register("format", format);
 }
 
 pub fn init(map: &mut Map) {
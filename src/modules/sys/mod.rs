@@ -11,6 +11,37 @@ fn same(map: &mut Map, arg: Value) -> Result<Value> {
     Ok(Value::Bool(a == b))
 }
 
+fn eq(map: &mut Map, arg: Value) -> Result<Value> {
+    let mut matcher = Matcher::listed().with_singles(&["a", "b"]);
+    matcher.mat_or_err(arg, map.line())?;
+    let mut list = matcher.to_values().unwrap();
+
+    let a = list.pop_front().unwrap();
+    let b = list.pop_front().unwrap();
+
+    Ok(Value::Bool(a.deep_eq(&b)))
+}
+
+fn cmp(map: &mut Map, arg: Value) -> Result<Value> {
+    let mut matcher = Matcher::listed().with_singles(&["a", "b"]);
+    matcher.mat_or_err(arg, map.line())?;
+    let mut list = matcher.to_values().unwrap();
+
+    let a = list.pop_front().unwrap();
+    let b = list.pop_front().unwrap();
+
+    Ok(Value::Int(a.deep_cmp(&b) as Int))
+}
+
+fn hash(map: &mut Map, arg: Value) -> Result<Value> {
+    let mut matcher = Matcher::single("a");
+    matcher.mat_or_err(arg, map.line())?;
+
+    let a = matcher.to_single().unwrap();
+
+    Ok(Value::Uint(a.stable_hash()))
+}
+
 fn is_stop(map: &mut Map, arg: Value) -> Result<Value> {
     let mut matcher = Matcher::single("a");
     matcher.mat_or_err(arg, map.line())?;
@@ -42,10 +73,64 @@ fn compile(map: &mut Map, arg: Value) -> Result<Value> {
     Ok(result)
 }
 
+/// Name of the type of the value, like `"int"`, `"str"`, `"weak"` or the name of a registered class
+fn type_of(map: &mut Map, arg: Value) -> Result<Value> {
+    let mut matcher = Matcher::single("value");
+    matcher.mat_or_err(arg, map.line())?;
+
+    let a = matcher.to_single().unwrap();
+
+    Ok(a.type_of(map.env()).into_owned().into_value(map))
+}
+
+/// Name of the type a weak reference, like a name passed as an argument, points to.
+/// Stop if the reference is dead, and the type of the value itself if it is not a weak reference
+fn target_type_of(map: &mut Map, arg: Value) -> Result<Value> {
+    let mut matcher = Matcher::single("value");
+    matcher.mat_or_err(arg, map.line())?;
+
+    let a = matcher.to_single().unwrap();
+
+    Ok(a.target_type_of(map.env())
+        .map(Cow::into_owned)
+        .into_value(map))
+}
+
+/// Documentation of a function or a map, from the `##` comments before it
+fn doc(map: &mut Map, arg: Value) -> Result<Value> {
+    let mut matcher = Matcher::single("value");
+    matcher.mat_or_err(arg, map.line())?;
+
+    let a = matcher.to_single().unwrap();
+
+    Ok(a.doc().into_value(map))
+}
+
+/// Types with an `is_<type>` predicate, `is_stop` is registered on its own
+const PREDICATES: &[&str] = &[
+    "int", "uint", "float", "bool", "null", "str", "vec", "map", "fn", "weak",
+];
+
 fn init_module(map: &mut Map) {
     map.register("same", same);
+    map.register("eq", eq);
+    map.register("cmp", cmp);
+    map.register("hash", hash);
     map.register("is_stop", is_stop);
     map.register("compile", compile);
+    map.register("type_of", type_of);
+    map.register("target_type_of", target_type_of);
+    map.register("doc", doc);
+    for name in PREDICATES {
+        map.register(format!("is_{}", name), move |map: &mut Map, arg: Value| {
+            let mut matcher = Matcher::single("value");
+            matcher.mat_or_err(arg, map.line())?;
+
+            let a = matcher.to_single().unwrap();
+
+            Ok(Value::Bool(a.type_of(map.env()) == *name))
+        });
+    }
 }
 
 pub fn init(map: &mut Map) {
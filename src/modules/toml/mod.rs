@@ -1,159 +1,204 @@
-use crate::prelude::*;
+//! TOML values for leas.
+//!
+//! Datetimes are resources of the `datetime` type, exposing their fields as properties.
+//! Both `null` and `stop` entries of tables are skipped when writing, since TOML has no null.
 
-impl TryInto<toml::Value> for &Value {
-    type Error = Error;
-    fn try_into(self) -> Result<toml::Value> {
-        match self {
-            Value::Bool(b) => Ok(toml::Value::Boolean(*b)),
-            Value::Float(f) => Ok(toml::Value::Float(*f)),
-            Value::Int(i) => Ok(toml::Value::Integer(*i)),
-            Value::Uint(u) => Ok(toml::Value::Integer(*u as i64)),
-            Value::Null => Ok(toml::Value::Float(f64::NAN)),
-            _ => (&self
-                .as_res()
-                .ok_or_else(|| Error::new("Invalid type for toml::Value", 0))?)
-                .try_into(),
+use crate::modules::format::{self, Format};
+use crate::prelude::*;
+use toml::value::{Datetime, Offset};
+
+/// `toml::Value` hands datetimes to serde as plain strings, so it is converted by hand to keep them.
+/// Tables are created under `under` to share its environment
+fn to_value(value: toml::Value, under: &Map) -> Value {
+    match value {
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Integer(i) => Value::Int(i),
+        toml::Value::Float(f) => Value::Float(f),
+        toml::Value::String(s) => s.into_value(under),
+        toml::Value::Datetime(datetime) => Value::Res(Resource::new(datetime)),
+        toml::Value::Array(a) => a
+            .into_iter()
+            .map(|value| to_value(value, under))
+            .collect::<Vec<_>>()
+            .into_value(under),
+        toml::Value::Table(t) => {
+            let mut map = Map::new_under(under);
+            for (key, value) in t {
+                map.forced_set(key, to_value(value, under));
+            }
+            Value::Res(Resource::new(map))
         }
     }
 }
 
-impl TryInto<toml::Value> for &Resource {
-    type Error = Error;
-    fn try_into(self) -> Result<toml::Value> {
-        if let Some(result) = self.visit(|s: &String| Ok(toml::Value::String(s.clone()))) {
-            return result;
-        }
-        if let Some(result) = self.visit(|v: &VecDeque<Value>| {
-            let mut vec = Vec::new();
-            for value in v {
-                vec.push(value.try_into()?);
-            }
-            Ok(toml::Value::Array(vec))
-        }) {
-            return result;
-        }
-        if let Some(result) = self.visit(|map: &Map| map.try_into()) {
-            return result;
-        }
-        Err(Error::new("Invalid type for toml::Value", 0))
-    }
+/// Datetimes are plain strings to serde, so values are converted by hand to keep them
+fn to_toml(value: &Value, env: &Env) -> Result<toml::Value> {
+    to_toml_guarded(value, env, &mut Vec::new())
 }
 
-impl TryInto<toml::Value> for &Map {
-    type Error = Error;
-    fn try_into(self) -> Result<toml::Value> {
-        let mut map = toml::value::Table::new();
-        for (key, value) in self.iter() {
-            map.insert(key.clone(), value.try_into()?);
+fn to_toml_guarded(value: &Value, env: &Env, visiting: &mut Vec<*const ()>) -> Result<toml::Value> {
+    let res = match value.as_res() {
+        Some(res) => res,
+        None => {
+            return toml::Value::try_from(sys::serializable(value, env))
+                .map_err(|err| Error::with_source(err, "When converting to toml value"))
         }
-        Ok(toml::Value::Table(map))
+    };
+    if let Some(datetime) = res.visit(|datetime: &Datetime| *datetime) {
+        return Ok(toml::Value::Datetime(datetime));
+    }
+
+    let ptr = res.as_ptr();
+    if visiting.contains(&ptr) {
+        return Err(Error::plain(
+            "Cannot convert value containing itself to toml value",
+        ));
     }
+    visiting.push(ptr);
+    let result = if let Some(entries) = res.visit(|map: &Map| {
+        map.iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect::<Vec<_>>()
+    }) {
+        entries
+            .into_iter()
+            .filter(|(_, value)| !matches!(value, Value::Null | Value::Stop))
+            .map(|(key, value)| Ok((key, to_toml_guarded(&value, env, visiting)?)))
+            .collect::<Result<_>>()
+            .map(toml::Value::Table)
+    } else if let Some(list) = res.visit(|list: &VecDeque<Value>| list.clone()) {
+        list.iter()
+            .map(|value| to_toml_guarded(value, env, visiting))
+            .collect::<Result<_>>()
+            .map(toml::Value::Array)
+    } else {
+        toml::Value::try_from(sys::serializable(value, env))
+            .map_err(|err| Error::with_source(err, "When converting to toml value"))
+    };
+    visiting.pop();
+    result
 }
 
-impl TryFrom<toml::Value> for Value {
-    type Error = Error;
-
-    fn try_from(value: toml::Value) -> Result<Value> {
-        match value {
-            toml::Value::Boolean(b) => Ok(Value::Bool(b)),
-            toml::Value::Integer(i) => Ok(Value::Int(i as i64)),
-            toml::Value::Float(f) => Ok(Value::Float(f)),
-            toml::Value::String(s) => Ok(Value::Res(Resource::new(s))),
-            toml::Value::Array(a) => {
-                let mut vec = VecDeque::new();
-                for value in a {
-                    vec.push_back(Value::try_from(value)?);
-                }
-                Ok(Value::Res(Resource::new(vec)))
-            }
-            toml::Value::Table(t) => {
-                let mut map = Map::new();
-                for (key, value) in t {
-                    map.forced_set(key, Value::try_from(value)?);
-                }
-                Ok(Value::Res(Resource::new(map)))
-            }
-            toml::Value::Datetime(_) => {
-                Err(Error::new("Converting from date time is not supported", 0))
-            }
-        }
+fn parse(s: &str, line: usize) -> Result<toml::Value> {
+    toml::from_str::<toml::Value>(s)
+        .map_err(|err| Error::new(format!("Invalid toml document\n{}", err), line))
+}
+
+/// Write a toml document, which must be a table
+fn document(value: &toml::Value, line: usize) -> Result<String> {
+    match value {
+        toml::Value::Table(_) => toml::to_string(value)
+            .map_err(|err| Error::with_source(err, "When converting to toml string")),
+        _ => Err(Error::new(
+            format!(
+                "Only tables can be written as toml documents, found {}",
+                value.type_str()
+            ),
+            line,
+        )),
     }
 }
 
-fn unwrap(map: &mut Map, arg: Value) -> Result<Value> {
-    let mut matcher = Matcher::single("toml");
-    matcher.mat_or_err(arg, map.line())?;
+impl Format for toml::Value {
+    const NAME: &'static str = "toml";
+
+    fn to_value(&self, under: &Map) -> Result<Value> {
+        Ok(to_value(self.clone(), under))
+    }
 
-    let toml_value = matcher.to_single().unwrap();
+    fn from_value(value: &Value, env: &Env) -> Result<Self> {
+        to_toml(value, env)
+    }
 
-    let line = map.line();
-    let value = toml_value.visit_res_or_else(
-        move |v: &toml::Value| Value::try_from(v.clone()),
-        move || {
-            Error::new(
-                "Non-toml value cannot be converted using this function",
-                line,
-            )
-        },
-    )??;
+    fn to_str(&self, line: usize) -> Result<String> {
+        document(self, line)
+    }
 
-    Ok(value)
+    fn from_str(s: &str, line: usize) -> Result<Self> {
+        parse(s, line)
+    }
 }
 
-fn from(map: &mut Map, arg: Value) -> Result<Value> {
-    let mut matcher = Matcher::single("value");
+fn load(map: &mut Map, arg: Value) -> Result<Value> {
+    let mut matcher = Matcher::single("path");
     matcher.mat_or_err(arg, map.line())?;
 
-    let value = matcher.to_single().unwrap();
+    let path = matcher.to_single().unwrap().extract::<String>(map.line())?;
 
-    let toml_value: toml::Value = (&value).try_into()?;
+    let content = std::fs::read_to_string(&path)
+        .map_err(|err| Error::with_source(err, format!("When reading toml file {:?}", path)))?;
+    let toml_value = parse(&content, map.line())
+        .map_err(|err| err.with(format!("When loading toml file {:?}", path)))?;
 
-    Ok(Value::Res(Resource::new(toml_value)))
+    Ok(to_value(toml_value, map))
 }
 
-fn to_str(map: &mut Map, arg: Value) -> Result<Value> {
-    let mut matcher = Matcher::single("toml");
+fn save(map: &mut Map, arg: Value) -> Result<Value> {
+    let mut matcher = Matcher::listed().with_singles(&["path", "value"]);
     matcher.mat_or_err(arg, map.line())?;
+    let mut list = matcher.to_values().unwrap();
 
-    let toml_value = matcher.to_single().unwrap();
+    let path = list.pop_front().unwrap().extract::<String>(map.line())?;
+    let value = list.pop_front().unwrap();
 
-    let line = map.line();
-    let value = toml_value.visit_res_or_else(
-        |v: &toml::Value| {
-            Ok(Value::Res(Resource::new(toml::to_string(v).map_err(
-                |err| Error::with_source(err, "When converting to toml string"),
-            )?)))
-        },
-        move || Error::new("Non-toml value cannot be converted to string", line),
-    )??;
+    let content = document(&to_toml(&value, map.env())?, map.line())?;
+    std::fs::write(&path, content)
+        .map_err(|err| Error::with_source(err, format!("When writing toml file {:?}", path)))?;
 
-    Ok(value)
+    Ok(Value::Null)
 }
 
-fn from_str(map: &mut Map, arg: Value) -> Result<Value> {
+fn datetime(map: &mut Map, arg: Value) -> Result<Value> {
     let mut matcher = Matcher::single("str");
     matcher.mat_or_err(arg, map.line())?;
 
-    let s = matcher.to_single().unwrap();
+    let s = matcher.to_single().unwrap().extract::<String>(map.line())?;
+
+    let datetime = s.parse::<Datetime>().map_err(|err| {
+        Error::new(
+            format!("Invalid toml datetime {:?}: {}", s, err),
+            map.line(),
+        )
+    })?;
 
-    let line = map.line();
-    let value = s.visit_res_or_else(
-        |s: &String| {
-            let toml_value = toml::from_str::<toml::Value>(s)
-                .map_err(|err| Error::with_source(err, "When converting string to toml value"))?;
-            Ok(Value::Res(Resource::new(toml_value)))
-        },
-        move || Error::new("Non-string value cannot be converted to toml value", line),
-    )??;
+    Ok(Value::Res(Resource::new(datetime)))
+}
 
-    Ok(value)
+fn datetime_class() -> Class {
+    Class::builder::<Datetime>("datetime")
+        .getter("date", |dt: &Datetime| dt.date.map(|date| date.to_string()))
+        .getter("time", |dt: &Datetime| dt.time.map(|time| time.to_string()))
+        .getter("offset", |dt: &Datetime| {
+            dt.offset.map(|offset| offset.to_string())
+        })
+        .getter("year", |dt: &Datetime| dt.date.map(|date| date.year))
+        .getter("month", |dt: &Datetime| dt.date.map(|date| date.month))
+        .getter("day", |dt: &Datetime| dt.date.map(|date| date.day))
+        .getter("hour", |dt: &Datetime| dt.time.map(|time| time.hour))
+        .getter("minute", |dt: &Datetime| dt.time.map(|time| time.minute))
+        .getter("second", |dt: &Datetime| dt.time.map(|time| time.second))
+        .getter("nanosecond", |dt: &Datetime| {
+            dt.time.map(|time| time.nanosecond)
+        })
+        .getter("offset_minutes", |dt: &Datetime| {
+            dt.offset.map(|offset| match offset {
+                Offset::Z => 0,
+                Offset::Custom { minutes } => minutes,
+            })
+        })
+        .method("to_str", |dt: &mut Datetime, map, _| {
+            Ok(dt.to_string().into_value(map))
+        })
+        .build()
 }
 
 fn init_module(map: &mut Map) {
-    map.register("unwrap", unwrap);
-    map.register("from", from);
-    map.register("to_str", to_str);
-    map.register("from_str", from_str);
+    format::register::<toml::Value>(map);
+    map.register("load", load);
+    map.register("save", save);
+    map.register("datetime", datetime);
+    map.register_class(datetime_class());
+    map.env().serialize_as_str::<Datetime>();
 }
 
 pub fn init(map: &mut Map) {
@@ -1,126 +1,203 @@
+use super::num::{radix_of, to_radix, zero_pad};
 use crate::prelude::*;
 
-fn add(map: &mut Map, arg: Value) -> Result<Value> {
-    let mut matcher = Matcher::listed().with_singles(&["a", "b"]);
-    matcher.mat_or_err(arg, map.line())?;
-    let mut list = matcher.to_values().unwrap();
+/// Error for an operation which has no result in range
+fn error(a: Uint, op: &str, b: Uint) -> Error {
+    if b == 0 && (op == "/" || op == "%") {
+        Error::plain(format!("Division by zero when computing {} {} 0", a, op))
+    } else {
+        Error::plain(format!(
+            "Unsigned integer overflow when computing {} {} {}",
+            a, op, b
+        ))
+    }
+}
+
+/// Wrapping and saturating operations still fail when dividing by zero
+fn zero_divisor(a: Uint, op: &str, b: Uint) -> Result<()> {
+    if b == 0 && (op == "/" || op == "%") {
+        Err(error(a, op, b))
+    } else {
+        Ok(())
+    }
+}
 
-    let a = list.pop_front().unwrap();
-    let b = list.pop_front().unwrap();
+macro_rules! checked {
+    ($($name: ident, $checked: ident => $op: literal;)*) => {$(
+        fn $name(a: Uint, b: Uint) -> Result<Uint> {
+            a.$checked(b).ok_or_else(|| error(a, $op, b))
+        }
 
-    a.as_uint()
-        .and_then(|a| b.as_uint().map(|b| a + b))
-        .map(Value::Uint)
-        .ok_or_else(move || Error::new("Arguments a and b are not unsigned integers", map.line()))
+        fn $checked(a: Uint, b: Uint) -> Result<Option<Uint>> {
+            Ok(a.$checked(b))
+        }
+    )*};
 }
 
-fn sub(map: &mut Map, arg: Value) -> Result<Value> {
-    let mut matcher = Matcher::listed().with_singles(&["a", "b"]);
-    matcher.mat_or_err(arg, map.line())?;
-    let mut list = matcher.to_values().unwrap();
+checked! {
+    add, checked_add => "+";
+    sub, checked_sub => "-";
+    mul, checked_mul => "*";
+    div, checked_div => "/";
+    rem, checked_rem => "%";
+}
 
-    let a = list.pop_front().unwrap();
-    let b = list.pop_front().unwrap();
+macro_rules! wrapping {
+    ($($name: ident => $op: literal;)*) => {$(
+        fn $name(a: Uint, b: Uint) -> Result<Uint> {
+            zero_divisor(a, $op, b)?;
+            Ok(a.$name(b))
+        }
+    )*};
+}
 
-    a.as_uint()
-        .and_then(|a| b.as_uint().map(|b| a - b))
-        .map(Value::Uint)
-        .ok_or_else(move || Error::new("Arguments a and b are not unsigned integers", map.line()))
+wrapping! {
+    wrapping_add => "+";
+    wrapping_sub => "-";
+    wrapping_mul => "*";
+    wrapping_div => "/";
+    wrapping_rem => "%";
+    saturating_add => "+";
+    saturating_sub => "-";
+    saturating_mul => "*";
+    saturating_div => "/";
 }
 
-fn mul(map: &mut Map, arg: Value) -> Result<Value> {
-    let mut matcher = Matcher::listed().with_singles(&["a", "b"]);
-    matcher.mat_or_err(arg, map.line())?;
-    let mut list = matcher.to_values().unwrap();
+fn pow(a: Uint, b: Uint) -> Result<Uint> {
+    u32::try_from(b)
+        .ok()
+        .and_then(|b| a.checked_pow(b))
+        .ok_or_else(|| {
+            Error::plain(format!(
+                "Unsigned integer overflow when computing {} to the power of {}",
+                a, b
+            ))
+        })
+}
 
-    let a = list.pop_front().unwrap();
-    let b = list.pop_front().unwrap();
+fn gcd(mut a: Uint, mut b: Uint) -> Result<Uint> {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    Ok(a)
+}
 
-    a.as_uint()
-        .and_then(|a| b.as_uint().map(|b| a * b))
-        .map(Value::Uint)
-        .ok_or_else(move || Error::new("Arguments a and b are not unsigned integers", map.line()))
+fn min(a: Uint, b: Uint) -> Result<Uint> {
+    Ok(a.min(b))
 }
 
-fn div(map: &mut Map, arg: Value) -> Result<Value> {
-    let mut matcher = Matcher::listed().with_singles(&["a", "b"]);
-    matcher.mat_or_err(arg, map.line())?;
-    let mut list = matcher.to_values().unwrap();
+fn max(a: Uint, b: Uint) -> Result<Uint> {
+    Ok(a.max(b))
+}
 
-    let a = list.pop_front().unwrap();
-    let b = list.pop_front().unwrap();
+fn and(a: Uint, b: Uint) -> Result<Uint> {
+    Ok(a & b)
+}
 
-    a.as_uint()
-        .and_then(|a| b.as_uint().map(|b| a / b))
-        .map(Value::Uint)
-        .ok_or_else(move || Error::new("Arguments a and b are not unsigned integers", map.line()))
+fn or(a: Uint, b: Uint) -> Result<Uint> {
+    Ok(a | b)
 }
 
-fn rem(map: &mut Map, arg: Value) -> Result<Value> {
-    let mut matcher = Matcher::listed().with_singles(&["a", "b"]);
-    matcher.mat_or_err(arg, map.line())?;
-    let mut list = matcher.to_values().unwrap();
+fn xor(a: Uint, b: Uint) -> Result<Uint> {
+    Ok(a ^ b)
+}
 
-    let a = list.pop_front().unwrap();
-    let b = list.pop_front().unwrap();
+fn not(n: Uint) -> Result<Uint> {
+    Ok(!n)
+}
 
-    a.as_uint()
-        .and_then(|a| b.as_uint().map(|b| a % b))
-        .map(Value::Uint)
-        .ok_or_else(move || Error::new("Arguments a and b are not unsigned integers", map.line()))
+macro_rules! shift {
+    ($($name: ident, $checked: ident => $direction: literal;)*) => {$(
+        fn $name(n: Uint, by: Uint) -> Result<Uint> {
+            u32::try_from(by)
+                .ok()
+                .and_then(|by| n.$checked(by))
+                .ok_or_else(|| Error::plain(format!("Cannot shift {} {} by {} bits", n, $direction, by)))
+        }
+    )*};
 }
 
-fn eq(map: &mut Map, arg: Value) -> Result<Value> {
-    let mut matcher = Matcher::listed().with_singles(&["a", "b"]);
-    matcher.mat_or_err(arg, map.line())?;
-    let mut list = matcher.to_values().unwrap();
+shift! {
+    shl, checked_shl => "left";
+    shr, checked_shr => "right";
+}
 
-    let a = list.pop_front().unwrap();
-    let b = list.pop_front().unwrap();
+fn eq(a: Uint, b: Uint) -> Result<Bool> {
+    Ok(a == b)
+}
 
-    a.as_uint()
-        .and_then(|a| b.as_uint().map(|b| a == b))
-        .map(Value::Bool)
-        .ok_or_else(move || Error::new("Arguments a and b are not unsigned integers", map.line()))
+fn lt(a: Uint, b: Uint) -> Result<Bool> {
+    Ok(a < b)
 }
 
-fn lt(map: &mut Map, arg: Value) -> Result<Value> {
-    let mut matcher = Matcher::listed().with_singles(&["a", "b"]);
-    matcher.mat_or_err(arg, map.line())?;
-    let mut list = matcher.to_values().unwrap();
+fn to_float(n: Uint) -> Result<Float> {
+    Ok(n as Float)
+}
 
-    let a = list.pop_front().unwrap();
-    let b = list.pop_front().unwrap();
+fn to_int(n: Uint) -> Result<Int> {
+    n.try_into()
+        .map_err(|_| Error::plain(format!("Unsigned integer {} is out of range of int", n)))
+}
+
+fn parse(s: String) -> Result<Option<Uint>> {
+    Ok(s.trim().parse().ok())
+}
+
+fn parse_radix(s: String, radix: Uint) -> Result<Option<Uint>> {
+    Ok(Uint::from_str_radix(s.trim(), radix_of(radix)?).ok())
+}
 
-    a.as_uint()
-        .and_then(|a| b.as_uint().map(|b| a < b))
-        .map(Value::Bool)
-        .ok_or_else(move || Error::new("Arguments a and b are not unsigned integers", map.line()))
+fn to_str(n: Uint) -> Result<String> {
+    Ok(n.to_string())
 }
 
-fn to_float(map: &mut Map, arg: Value) -> Result<Value> {
-    arg.as_uint()
-        .map(|n| Value::Float(n as Float))
-        .ok_or_else(|| Error::new("Argument is not an unsigned integer", map.line()))
+fn to_str_radix(n: Uint, radix: Uint) -> Result<String> {
+    Ok(to_radix(n, radix_of(radix)?))
 }
 
-fn to_int(map: &mut Map, arg: Value) -> Result<Value> {
-    arg.as_uint()
-        .and_then(|n| n.try_into().ok())
-        .map(Value::Int)
-        .ok_or_else(|| Error::new("Argument is not an unsigned integer", map.line()))
+fn to_str_padded(n: Uint, width: Uint) -> Result<String> {
+    zero_pad(n.to_string(), width)
 }
 
 fn init_module(map: &mut Map) {
-    map.register("add", add);
-    map.register("sub", sub);
-    map.register("mul", mul);
-    map.register("div", div);
-    map.register("mod", rem);
-    map.register("eq", eq);
-    map.register("lt", lt);
-    map.register("to_float", to_float);
-    map.register("to_int", to_int);
+    map.register_fn("add", add);
+    map.register_fn("sub", sub);
+    map.register_fn("mul", mul);
+    map.register_fn("div", div);
+    map.register_fn("mod", rem);
+    map.register_fn("checked_add", checked_add);
+    map.register_fn("checked_sub", checked_sub);
+    map.register_fn("checked_mul", checked_mul);
+    map.register_fn("checked_div", checked_div);
+    map.register_fn("checked_mod", checked_rem);
+    map.register_fn("wrapping_add", wrapping_add);
+    map.register_fn("wrapping_sub", wrapping_sub);
+    map.register_fn("wrapping_mul", wrapping_mul);
+    map.register_fn("wrapping_div", wrapping_div);
+    map.register_fn("wrapping_mod", wrapping_rem);
+    map.register_fn("saturating_add", saturating_add);
+    map.register_fn("saturating_sub", saturating_sub);
+    map.register_fn("saturating_mul", saturating_mul);
+    map.register_fn("saturating_div", saturating_div);
+    map.register_fn("pow", pow);
+    map.register_fn("gcd", gcd);
+    map.register_fn("min", min);
+    map.register_fn("max", max);
+    map.register_fn("and", and);
+    map.register_fn("or", or);
+    map.register_fn("xor", xor);
+    map.register_fn("not", not);
+    map.register_fn("shl", shl);
+    map.register_fn("shr", shr);
+    map.register_fn("eq", eq);
+    map.register_fn("lt", lt);
+    map.register_fn("to_float", to_float);
+    map.register_fn("to_int", to_int);
+    map.register_fn("parse", parse);
+    map.register_fn("parse_radix", parse_radix);
+    map.register_fn("to_str", to_str);
+    map.register_fn("to_str_radix", to_str_radix);
+    map.register_fn("to_str_padded", to_str_padded);
 }
 
 pub fn init(map: &mut Map) {
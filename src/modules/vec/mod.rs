@@ -115,7 +115,7 @@ fn set(map: &mut Map, arg: Value) -> Result<Value> {
         as usize;
     vec.visit_mut_res_or_else(
         move |vec: &mut VecDeque<Value>| {
-            if index > vec.len() {
+            if index >= vec.len() {
                 vec.resize(index + 1, Value::Stop);
             }
             vec[index] = value;
@@ -156,7 +156,7 @@ fn extend(map: &mut Map, arg: Value) -> Result<Value> {
     )?;
     let line = map.line();
     vec.visit_mut_res_or_else(
-        move |vec: &mut VecDeque<Value>| vec.extend(q.into_iter()),
+        move |vec: &mut VecDeque<Value>| vec.extend(q),
         move || Error::new("Argument vec is not a vector", line),
     )?;
 
@@ -1,11 +1,13 @@
-pub use crate::{comp, rt, sys};
+pub use crate::{comp, interp, rt, sys};
 
 pub(crate) use comp::*;
+pub(crate) use indexmap::IndexMap;
 pub(crate) use std::any::Any;
 pub(crate) use std::borrow::Cow;
 pub use std::cell::{Cell, Ref, RefCell, RefMut};
 pub(crate) use std::collections::{HashMap, HashSet, VecDeque};
 pub(crate) use std::fmt;
+pub(crate) use std::io::Write;
 pub(crate) use std::path::{Path, PathBuf};
 pub(crate) use std::rc::{Rc, Weak};
 pub(crate) use sys::*;
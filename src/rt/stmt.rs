@@ -40,15 +40,13 @@ impl Stmt {
                     .map_err(|err| err.with(format!("When compiling module {:?}", path)))?;
 
                 let mut new_map = Map::new_under(map);
-                unsafe {
-                    new_map.link_to(map);
-                }
+                new_map.link_to(map);
                 new_map.env().forward_base(path.clone());
-                let result = stmt
-                    .eval(&mut new_map)
-                    .map_err(|err| err.with(format!("When evaluating module {:?}", path)))?;
+                let result = stmt.eval(&mut new_map);
                 new_map.env().backward_base();
                 new_map.unlink_to(map);
+                let result =
+                    result.map_err(|err| err.with(format!("When evaluating module {:?}", path)))?;
 
                 let res_map = Resource::new(new_map);
                 map.env().set_import(path, &res_map);
@@ -101,9 +99,7 @@ impl Stmt {
     fn eval_map(map: &mut Map, opd: &Self) -> Result<Value> {
         let mut new_map = Map::new_under(map);
 
-        unsafe {
-            new_map.link_to(map);
-        }
+        new_map.link_to(map);
         let result = opd.eval(&mut new_map);
         new_map.unlink_to(map);
 
@@ -121,9 +117,7 @@ impl Stmt {
         left.as_res()
             .ok_or_else(|| Error::new(format!("Cannot enter non-resource {}", left), map.line()))?
             .visit_mut(|inner_map: &mut Map| {
-                unsafe {
-                    inner_map.link_to(map);
-                }
+                inner_map.link_to(map);
                 inner_map.snapshot();
                 let result = right.eval(inner_map);
                 inner_map.rollback();
@@ -233,8 +227,28 @@ impl Stmt {
         Ok(result)
     }
 
+    /// Dispatch `obj:method(arg)` to the class of `obj`, if it is registered and has the method
+    fn eval_method(map: &mut Map, this: &Value, right: &Self) -> Option<Result<Value>> {
+        let res = this.as_res()?;
+        let class = map.env().class_of(&res)?;
+        let (name, arg) = match right {
+            Self::Call(name, arg) => (name.as_word(map)?, arg),
+            _ => return None,
+        };
+        if !class.has_method(&name) {
+            return None;
+        }
+        Some(
+            arg.eval(map)
+                .and_then(|arg| class.call(&res, &name, map, arg)),
+        )
+    }
+
     fn eval_colon(map: &mut Map, left: &Self, right: &Self) -> Result<Value> {
         let left = left.eval(map)?;
+        if let Some(result) = Self::eval_method(map, &left, right) {
+            return result;
+        }
         map.push_name("this", left);
         let result = right.eval(map);
         map.pop_name("this");
@@ -254,7 +268,7 @@ impl Eval for Stmt {
                 for stmt in block {
                     match stmt {
                         Self::Return(value) => {
-                            return Ok(value.eval(map)?);
+                            return value.eval(map);
                         }
                         _ => {
                             result = stmt.eval(map)?;
@@ -278,6 +292,11 @@ impl Eval for Stmt {
             }
             Self::Map(opd) => Self::eval_map(map, opd),
             Self::Fn(body) => Self::eval_fn(map, body),
+            Self::Doc(doc, opd) => {
+                let value = opd.eval(map)?;
+                value.set_doc(doc);
+                Ok(value)
+            }
             Self::Neg(_opd) => {
                 todo!()
             }
@@ -322,6 +341,10 @@ impl Eval for Stmt {
                         map.line(),
                     )
                 })?;
+                if let Some(class) = map.env().class_of(&res) {
+                    let name = right.as_word_or_string(map)?;
+                    return class.get(&res, &name, map);
+                }
                 match res
                     .visit_mut(|map: &mut Map| right.get(map))
                     .ok_or_else(|| {
@@ -334,15 +357,12 @@ impl Eval for Stmt {
                     Err(err) => {
                         res.visit_mut(|map: &mut Map| {
                             map.get("meta")
-                                .ok_or_else(|| err)?
+                                .ok_or(err)?
                                 .as_res()
                                 .ok_or_else(|| {
                                     Error::new("\"meta\" is found, but is not a map", map.line())
                                 })?
-                                .visit_mut(|map: &mut Map| {
-                                    let result = right.get(map);
-                                    result
-                                })
+                                .visit_mut(|map: &mut Map| right.get(map))
                                 .ok_or_else(|| {
                                     Error::new(
                                         format!("Cannot get value from dot left(although \"meta\" map found) {:?}", left),
@@ -399,11 +419,15 @@ impl Eval for Stmt {
             }
             Self::Dot(left, right) => {
                 let left = left.eval(map)?;
-                left.as_res()
-                    .ok_or_else(|| {
-                        Error::new(format!("Cannot set value to dot left {}", left), map.line())
-                    })?
-                    .visit_mut(|map: &mut Map| right.set(map, value))
+                let res = left.as_res().ok_or_else(|| {
+                    Error::new(format!("Cannot set value to dot left {}", left), map.line())
+                })?;
+                if let Some(class) = map.env().class_of(&res) {
+                    let name = right.as_word_or_string(map)?;
+                    class.set(&res, &name, value.clone(), map.line())?;
+                    return Ok(value);
+                }
+                res.visit_mut(|map: &mut Map| right.set(map, value))
                     .ok_or_else(|| {
                         Error::new(format!("Cannot set value to dot left {}", left), map.line())
                     })?
@@ -6,9 +6,24 @@ impl Eval for Token {
             Self::Int(value) => Ok(Value::Int(*value)),
             Self::Float(value) => Ok(Value::Float(*value)),
             Self::Uint(value) => Ok(Value::Uint(*value)),
+            #[cfg(feature = "bigint")]
+            Self::Big(value) => Ok(Value::Res(Resource::new(value.clone()))),
             Self::Bool(value) => Ok(Value::Bool(*value)),
             Self::Null => Ok(Value::Null),
             Self::Str(value) => Ok(Value::Res(Resource::new(value.clone()))),
+            Self::Interp(fragments) => {
+                let mut result = String::new();
+                for fragment in fragments.iter() {
+                    match fragment {
+                        Fragment::Str(str) => result.push_str(str),
+                        Fragment::Expr(stmt) => {
+                            let value = stmt.eval(map)?;
+                            result.push_str(&value.to_str(map)?);
+                        }
+                    }
+                }
+                Ok(Value::Res(Resource::new(result)))
+            }
             Self::Word(_) => self.get(map),
             Self::Stop => Ok(Value::Stop),
             _ => Err(Error::new(
@@ -0,0 +1,205 @@
+use super::*;
+use std::any::TypeId;
+use std::marker::PhantomData;
+
+type Method = Rc<dyn Fn(&Resource, &mut Map, Value) -> Result<Value>>;
+type Getter = Rc<dyn Fn(&Resource, &Map) -> Result<Value>>;
+type Setter = Rc<dyn Fn(&Resource, Value, usize) -> Result<()>>;
+type Entries = Rc<dyn Fn(&Resource) -> Option<Vec<(Value, Value)>>>;
+
+/// Methods and properties of a Rust type exposed to scripts.
+/// Resources holding the type dispatch `obj.name` and `obj:name(arg)` here once the class is registered
+pub struct Class {
+    name: String,
+    type_id: TypeId,
+    methods: HashMap<String, Method>,
+    getters: HashMap<String, Getter>,
+    setters: HashMap<String, Setter>,
+    entries: Option<Entries>,
+}
+
+pub struct ClassBuilder<T> {
+    class: Class,
+    _marker: PhantomData<T>,
+}
+
+impl Class {
+    pub fn builder<T: Res + 'static>(name: impl ToString) -> ClassBuilder<T> {
+        ClassBuilder {
+            class: Class {
+                name: name.to_string(),
+                type_id: TypeId::of::<RefCell<T>>(),
+                methods: HashMap::new(),
+                getters: HashMap::new(),
+                setters: HashMap::new(),
+                entries: None,
+            },
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+
+    pub fn has_method(&self, name: &str) -> bool {
+        self.methods.contains_key(name)
+    }
+
+    /// Entries of the instance to write it like a map, if the class is a collection
+    pub fn entries(&self, res: &Resource) -> Option<Vec<(Value, Value)>> {
+        self.entries.as_ref()?(res)
+    }
+
+    /// Call a method on the instance
+    pub fn call(&self, res: &Resource, name: &str, map: &mut Map, arg: Value) -> Result<Value> {
+        let method = self.methods.get(name).ok_or_else(|| {
+            Error::new(
+                format!("Type {} has no method named {:?}", self.name, name),
+                map.line(),
+            )
+        })?;
+        method(res, map, arg)
+    }
+
+    /// Get a property of the instance, or a method bound to it
+    pub fn get(&self, res: &Resource, name: &str, map: &Map) -> Result<Value> {
+        if let Some(getter) = self.getters.get(name) {
+            return getter(res, map);
+        }
+        if let Some(method) = self.methods.get(name) {
+            let method = method.clone();
+            let res = res.clone();
+            return Ok(Value::Res(Resource::new_func(Func::new(
+                move |map, arg| method(&res, map, arg),
+                format!("{}.{}", self.name, name),
+            ))));
+        }
+        Err(Error::new(
+            format!("Type {} has no property named {:?}", self.name, name),
+            map.line(),
+        ))
+    }
+
+    pub fn set(&self, res: &Resource, name: &str, value: Value, line: usize) -> Result<()> {
+        let setter = self.setters.get(name).ok_or_else(|| {
+            Error::new(
+                format!("Property {:?} of type {} cannot be set", name, self.name),
+                line,
+            )
+        })?;
+        setter(res, value, line)
+    }
+}
+
+impl<T: Res + 'static> ClassBuilder<T> {
+    fn mismatch(class: &str, line: usize) -> Error {
+        Error::new(format!("Value is not an instance of {}", class), line)
+    }
+
+    /// The instance is borrowed by one of its methods, which called back into a script using it
+    fn in_use(class: &str, line: usize) -> Error {
+        Error::new(
+            format!(
+                "Instance of {} cannot be used while one of its methods is running",
+                class
+            ),
+            line,
+        )
+    }
+
+    fn cell<'a>(res: &'a Resource, class: &str, line: usize) -> Result<&'a RefCell<T>> {
+        res.cell::<T>().ok_or_else(|| Self::mismatch(class, line))
+    }
+
+    /// Add a method, called with the instance borrowed mutably.
+    /// Using the instance again before the method returns, like from a callback, is an error
+    pub fn method<F>(mut self, name: impl ToString, f: F) -> Self
+    where
+        F: Fn(&mut T, &mut Map, Value) -> Result<Value> + 'static,
+    {
+        let class = self.class.name.clone();
+        self.class.methods.insert(
+            name.to_string(),
+            Rc::new(move |res, map, arg| {
+                let line = map.line();
+                let mut value = Self::cell(res, &class, line)?
+                    .try_borrow_mut()
+                    .map_err(|_| Self::in_use(&class, line))?;
+                f(&mut value, map, arg)
+            }),
+        );
+        self
+    }
+
+    /// Add a readable property
+    pub fn getter<F, R>(mut self, name: impl ToString, f: F) -> Self
+    where
+        F: Fn(&T) -> R + 'static,
+        R: IntoValue + 'static,
+    {
+        let class = self.class.name.clone();
+        self.class.getters.insert(
+            name.to_string(),
+            Rc::new(move |res, map| {
+                let value = Self::cell(res, &class, map.line())?
+                    .try_borrow()
+                    .map_err(|_| Self::in_use(&class, map.line()))?;
+                Ok(f(&value).into_value(map))
+            }),
+        );
+        self
+    }
+
+    /// Add a writable property, converting the assigned value first
+    pub fn setter<F, V>(mut self, name: impl ToString, f: F) -> Self
+    where
+        F: Fn(&mut T, V) + 'static,
+        V: FromValue + 'static,
+    {
+        let class = self.class.name.clone();
+        self.class.setters.insert(
+            name.to_string(),
+            Rc::new(move |res, value, line| {
+                let value = value.extract::<V>(line)?;
+                let mut target = Self::cell(res, &class, line)?
+                    .try_borrow_mut()
+                    .map_err(|_| Self::in_use(&class, line))?;
+                f(&mut target, value);
+                Ok(())
+            }),
+        );
+        self
+    }
+
+    /// Make the instances collections, written with their entries when converted to strings
+    pub fn entries<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&T) -> Vec<(Value, Value)> + 'static,
+    {
+        self.class.entries = Some(Rc::new(move |res| {
+            let value = res.cell::<T>()?.try_borrow().ok()?;
+            Some(f(&value))
+        }));
+        self
+    }
+
+    pub fn build(self) -> Class {
+        self.class
+    }
+}
+
+impl fmt::Debug for Class {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Class")
+            .field("name", &self.name)
+            .field("methods", &self.methods.keys().collect::<Vec<_>>())
+            .field("getters", &self.getters.keys().collect::<Vec<_>>())
+            .field("setters", &self.setters.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
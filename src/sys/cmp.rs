@@ -0,0 +1,276 @@
+//! Structural equality, ordering and hashing of values
+//!
+//! Strings, vectors, maps and dicts are compared by content, other resources by identity.
+//! Floats are compared bitwise with `f64::total_cmp`, so `nan` equals itself.
+//! Values of different types are ordered by type first: null, stop, bool, int, uint,
+//! float, bigint, str, vec, map, dict and then other resources.
+//! Cyclic values are handled by treating a pair of resources already being compared as equal.
+
+use super::*;
+#[cfg(feature = "dict")]
+use crate::modules::dict::Dict;
+use std::cell::Ref;
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+
+/// Contents of a resource compared by content, borrowed for the time of the comparison
+enum Shape<'a> {
+    #[cfg(feature = "bigint")]
+    Big(Ref<'a, Big>),
+    Str(Ref<'a, String>),
+    Vec(Ref<'a, VecDeque<Value>>),
+    Map(Ref<'a, Map>),
+    #[cfg(feature = "dict")]
+    Dict(Ref<'a, Dict>),
+    Other,
+}
+
+impl<'a> Shape<'a> {
+    fn of(res: &'a Resource) -> Self {
+        #[cfg(feature = "bigint")]
+        if let Some(n) = res.borrow() {
+            return Self::Big(n);
+        }
+        #[cfg(feature = "dict")]
+        if let Some(dict) = res.borrow() {
+            return Self::Dict(dict);
+        }
+        if let Some(str) = res.borrow() {
+            Self::Str(str)
+        } else if let Some(vec) = res.borrow() {
+            Self::Vec(vec)
+        } else if let Some(map) = res.borrow() {
+            Self::Map(map)
+        } else {
+            Self::Other
+        }
+    }
+}
+
+/// Entries sorted by key, as the order they were set in does not matter
+fn sorted<'a, K: Ord>(entries: impl Iterator<Item = (K, &'a Value)>) -> Vec<(K, &'a Value)> {
+    let mut entries = entries.collect::<Vec<_>>();
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    entries
+}
+
+fn rank(value: &Value) -> u8 {
+    match value {
+        Value::Null => 0,
+        Value::Stop => 1,
+        Value::Bool(_) => 2,
+        Value::Int(_) => 3,
+        Value::Uint(_) => 4,
+        Value::Float(_) => 5,
+        Value::Res(_) | Value::Weak(_) => match value.as_res() {
+            #[cfg(feature = "bigint")]
+            Some(res) if res.visit(|_: &Big| ()).is_some() => 6,
+            Some(res) if res.visit(|_: &String| ()).is_some() => 7,
+            Some(res) if res.visit(|_: &VecDeque<Value>| ()).is_some() => 8,
+            Some(res) if res.visit(|_: &Map| ()).is_some() => 9,
+            #[cfg(feature = "dict")]
+            Some(res) if res.visit(|_: &Dict| ()).is_some() => 10,
+            _ => 11,
+        },
+    }
+}
+
+fn address(value: &Value) -> usize {
+    value.as_res().map(|res| res.as_ptr() as usize).unwrap_or(0)
+}
+
+fn cmp_guarded(a: &Value, b: &Value, visiting: &mut Vec<(usize, usize)>) -> Ordering {
+    match (a, b) {
+        (Value::Null, Value::Null) | (Value::Stop, Value::Stop) => Ordering::Equal,
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        (Value::Int(a), Value::Int(b)) => a.cmp(b),
+        (Value::Uint(a), Value::Uint(b)) => a.cmp(b),
+        (Value::Float(a), Value::Float(b)) => a.total_cmp(b),
+        (Value::Res(_) | Value::Weak(_), Value::Res(_) | Value::Weak(_)) => {
+            let pair = (address(a), address(b));
+            if pair.0 == pair.1 || visiting.contains(&pair) {
+                return Ordering::Equal;
+            }
+            let (Some(left), Some(right)) = (a.as_res(), b.as_res()) else {
+                return pair.0.cmp(&pair.1);
+            };
+
+            visiting.push(pair);
+            let result = match (Shape::of(&left), Shape::of(&right)) {
+                #[cfg(feature = "bigint")]
+                (Shape::Big(a), Shape::Big(b)) => a.cmp(&*b),
+                (Shape::Str(a), Shape::Str(b)) => a.cmp(&*b),
+                (Shape::Vec(a), Shape::Vec(b)) => {
+                    cmp_seq(a.iter().zip(b.iter()), a.len().cmp(&b.len()), visiting)
+                }
+                (Shape::Map(a), Shape::Map(b)) => {
+                    cmp_entries(&sorted(a.iter()), &sorted(b.iter()), visiting)
+                }
+                #[cfg(feature = "dict")]
+                (Shape::Dict(a), Shape::Dict(b)) => {
+                    cmp_entries(&sorted(a.iter()), &sorted(b.iter()), visiting)
+                }
+                (Shape::Other, Shape::Other) => pair.0.cmp(&pair.1),
+                _ => rank(a).cmp(&rank(b)),
+            };
+            visiting.pop();
+            result
+        }
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+fn cmp_seq<'a>(
+    pairs: impl Iterator<Item = (&'a Value, &'a Value)>,
+    otherwise: Ordering,
+    visiting: &mut Vec<(usize, usize)>,
+) -> Ordering {
+    for (a, b) in pairs {
+        match cmp_guarded(a, b, visiting) {
+            Ordering::Equal => {}
+            result => return result,
+        }
+    }
+    otherwise
+}
+
+/// Compare entries sorted by key, first by their keys and then by their values
+fn cmp_entries<K: Ord>(
+    a: &[(K, &Value)],
+    b: &[(K, &Value)],
+    visiting: &mut Vec<(usize, usize)>,
+) -> Ordering {
+    let keys = a.iter().map(|(k, _)| k).cmp(b.iter().map(|(k, _)| k));
+    if keys == Ordering::Equal {
+        cmp_seq(
+            a.iter().map(|(_, v)| *v).zip(b.iter().map(|(_, v)| *v)),
+            Ordering::Equal,
+            visiting,
+        )
+    } else {
+        keys
+    }
+}
+
+fn hash_guarded<H: Hasher>(value: &Value, state: &mut H, visiting: &mut Vec<usize>) {
+    rank(value).hash(state);
+    match value {
+        Value::Null | Value::Stop => {}
+        Value::Bool(value) => value.hash(state),
+        Value::Int(value) => value.hash(state),
+        Value::Uint(value) => value.hash(state),
+        Value::Float(value) => value.to_bits().hash(state),
+        Value::Res(_) | Value::Weak(_) => {
+            let address = address(value);
+            if visiting.contains(&address) {
+                return;
+            }
+            let Some(res) = value.as_res() else {
+                return;
+            };
+
+            visiting.push(address);
+            match Shape::of(&res) {
+                #[cfg(feature = "bigint")]
+                Shape::Big(n) => n.hash(state),
+                Shape::Str(str) => str.hash(state),
+                Shape::Vec(vec) => {
+                    vec.len().hash(state);
+                    for value in vec.iter() {
+                        hash_guarded(value, state, visiting);
+                    }
+                }
+                Shape::Map(map) => hash_entries(&sorted(map.iter()), state, visiting),
+                #[cfg(feature = "dict")]
+                Shape::Dict(dict) => hash_entries(&sorted(dict.iter()), state, visiting),
+                Shape::Other => address.hash(state),
+            }
+            visiting.pop();
+        }
+    }
+}
+
+fn hash_entries<K: Hash, H: Hasher>(
+    entries: &[(K, &Value)],
+    state: &mut H,
+    visiting: &mut Vec<usize>,
+) {
+    entries.len().hash(state);
+    for (key, value) in entries {
+        key.hash(state);
+        hash_guarded(value, state, visiting);
+    }
+}
+
+/// FxHash, which unlike `DefaultHasher` is fixed, so hashes do not change with the Rust version.
+/// Integers are taken as little endian 64 bit words whatever the platform
+#[derive(Default)]
+struct FxHasher(u64);
+
+impl FxHasher {
+    const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+    fn add(&mut self, word: u64) {
+        self.0 = (self.0.rotate_left(5) ^ word).wrapping_mul(Self::SEED);
+    }
+}
+
+impl Hasher for FxHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        let mut words = bytes.chunks_exact(8);
+        for word in &mut words {
+            self.add(u64::from_le_bytes(word.try_into().unwrap()));
+        }
+        for byte in words.remainder() {
+            self.add(*byte as u64);
+        }
+    }
+
+    fn write_u8(&mut self, n: u8) {
+        self.add(n as u64);
+    }
+
+    fn write_u16(&mut self, n: u16) {
+        self.add(n as u64);
+    }
+
+    fn write_u32(&mut self, n: u32) {
+        self.add(n as u64);
+    }
+
+    fn write_u64(&mut self, n: u64) {
+        self.add(n);
+    }
+
+    fn write_usize(&mut self, n: usize) {
+        self.add(n as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl Value {
+    /// Structural equality, see the module documentation
+    pub fn deep_eq(&self, other: &Value) -> bool {
+        self.deep_cmp(other) == Ordering::Equal
+    }
+
+    /// Total ordering consistent with `deep_eq`
+    pub fn deep_cmp(&self, other: &Value) -> Ordering {
+        cmp_guarded(self, other, &mut Vec::new())
+    }
+
+    /// Hash consistent with `deep_eq`
+    pub fn deep_hash<H: Hasher>(&self, state: &mut H) {
+        hash_guarded(self, state, &mut Vec::new())
+    }
+
+    /// Hash which stays the same between runs, except for resources compared by identity
+    pub fn stable_hash(&self) -> u64 {
+        let mut hasher = FxHasher::default();
+        self.deep_hash(&mut hasher);
+        hasher.finish()
+    }
+}
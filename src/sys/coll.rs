@@ -1,8 +1,12 @@
 use super::*;
 
+pub type FuncBody = Box<dyn FnMut(&mut Map, Value) -> Result<Value>>;
+
 pub struct Func {
-    pub f: Box<dyn FnMut(&mut Map, Value) -> Result<Value>>,
+    pub f: FuncBody,
     pub name: Option<String>,
+    /// Documentation from the `##` comments before the function
+    pub doc: Option<String>,
 }
 
 impl Func {
@@ -10,6 +14,7 @@ impl Func {
         Self {
             f: Box::new(f),
             name: Some(name),
+            doc: None,
         }
     }
 
@@ -17,6 +22,7 @@ impl Func {
         Self {
             f: Box::new(f),
             name: None,
+            doc: None,
         }
     }
 }
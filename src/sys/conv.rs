@@ -0,0 +1,392 @@
+use super::*;
+
+/// Conversion from a leas value to a Rust value
+pub trait FromValue: Sized {
+    /// Name of the expected value, used in argument shapes and error messages
+    fn type_name() -> Cow<'static, str>;
+
+    fn from_value(value: &Value) -> Option<Self>;
+}
+
+/// Conversion from a Rust value to a leas value
+pub trait IntoValue {
+    /// Convert the value, creating maps under `under` so that they share its environment
+    fn into_value(self, under: &Map) -> Value;
+}
+
+/// A plain Rust function whose argument shape is derived from its parameter types
+pub trait NativeFn<Args> {
+    fn shape() -> String;
+
+    /// Call the function from `map`, which gives the line and the environment of the result
+    fn call_native(&mut self, map: &Map, arg: Value) -> Result<Value>;
+}
+
+impl Value {
+    /// Convert the value to a Rust value, failing with an error at the given line
+    pub fn extract<T: FromValue>(&self, line: usize) -> Result<T> {
+        T::from_value(self)
+            .ok_or_else(|| Error::new(format!("Expected {}, found {}", T::type_name(), self), line))
+    }
+
+    pub fn to_list(&self) -> Option<VecDeque<Value>> {
+        self.as_res()?.visit(|list: &VecDeque<Value>| list.clone())
+    }
+}
+
+impl FromValue for Value {
+    fn type_name() -> Cow<'static, str> {
+        Cow::Borrowed("any")
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        Some(value.clone())
+    }
+}
+
+impl IntoValue for Value {
+    fn into_value(self, _: &Map) -> Value {
+        self
+    }
+}
+
+impl FromValue for Resource {
+    fn type_name() -> Cow<'static, str> {
+        Cow::Borrowed("resource")
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        value.as_res()
+    }
+}
+
+impl IntoValue for Resource {
+    fn into_value(self, _: &Map) -> Value {
+        Value::Res(self)
+    }
+}
+
+macro_rules! impl_number {
+    ($variant: ident, $as: ident, $name: literal, $($ty: ty),*) => {
+        $(
+            impl FromValue for $ty {
+                fn type_name() -> Cow<'static, str> {
+                    Cow::Borrowed($name)
+                }
+
+                fn from_value(value: &Value) -> Option<Self> {
+                    value.$as()?.try_into().ok()
+                }
+            }
+
+            impl IntoValue for $ty {
+                fn into_value(self, _: &Map) -> Value {
+                    Value::$variant(self as $variant)
+                }
+            }
+        )*
+    };
+}
+
+impl_number!(Int, as_int, "int", i8, i16, i32, i64, isize);
+impl_number!(Uint, as_uint, "uint", u8, u16, u32, u64, usize);
+
+impl FromValue for f64 {
+    fn type_name() -> Cow<'static, str> {
+        Cow::Borrowed("float")
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        value.as_float()
+    }
+}
+
+impl IntoValue for f64 {
+    fn into_value(self, _: &Map) -> Value {
+        Value::Float(self)
+    }
+}
+
+impl FromValue for f32 {
+    fn type_name() -> Cow<'static, str> {
+        Cow::Borrowed("float")
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        value.as_float().map(|f| f as f32)
+    }
+}
+
+impl IntoValue for f32 {
+    fn into_value(self, _: &Map) -> Value {
+        Value::Float(self as Float)
+    }
+}
+
+impl FromValue for bool {
+    fn type_name() -> Cow<'static, str> {
+        Cow::Borrowed("bool")
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        value.as_bool()
+    }
+}
+
+impl IntoValue for bool {
+    fn into_value(self, _: &Map) -> Value {
+        Value::Bool(self)
+    }
+}
+
+impl FromValue for () {
+    fn type_name() -> Cow<'static, str> {
+        Cow::Borrowed("null")
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        value.as_null()
+    }
+}
+
+impl IntoValue for () {
+    fn into_value(self, _: &Map) -> Value {
+        Value::Null
+    }
+}
+
+/// Characters are uints holding their code point
+impl FromValue for char {
+    fn type_name() -> Cow<'static, str> {
+        Cow::Borrowed("char")
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        char::from_u32(value.as_uint()?.try_into().ok()?)
+    }
+}
+
+impl IntoValue for char {
+    fn into_value(self, _: &Map) -> Value {
+        Value::Uint(self as Uint)
+    }
+}
+
+impl FromValue for String {
+    fn type_name() -> Cow<'static, str> {
+        Cow::Borrowed("str")
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        value.as_res()?.visit(|s: &String| s.clone())
+    }
+}
+
+impl IntoValue for String {
+    fn into_value(self, _: &Map) -> Value {
+        Value::Res(Resource::new(self))
+    }
+}
+
+impl IntoValue for &str {
+    fn into_value(self, _: &Map) -> Value {
+        Value::Res(Resource::new(self.to_string()))
+    }
+}
+
+/// `stop` stands for a missing value, just like what native functions return on failure
+impl<T: FromValue> FromValue for Option<T> {
+    fn type_name() -> Cow<'static, str> {
+        Cow::Owned(format!("{} or stop", T::type_name()))
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Stop => Some(None),
+            _ => T::from_value(value).map(Some),
+        }
+    }
+}
+
+impl<T: IntoValue> IntoValue for Option<T> {
+    fn into_value(self, under: &Map) -> Value {
+        match self {
+            Some(value) => value.into_value(under),
+            None => Value::Stop,
+        }
+    }
+}
+
+impl<T: FromValue> FromValue for VecDeque<T> {
+    fn type_name() -> Cow<'static, str> {
+        Cow::Owned(format!("vec of {}", T::type_name()))
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        value.to_list()?.iter().map(T::from_value).collect()
+    }
+}
+
+impl<T: IntoValue> IntoValue for VecDeque<T> {
+    fn into_value(self, under: &Map) -> Value {
+        let list = self
+            .into_iter()
+            .map(|value| value.into_value(under))
+            .collect::<VecDeque<_>>();
+        Value::Res(Resource::new(list))
+    }
+}
+
+impl<T: FromValue> FromValue for Vec<T> {
+    fn type_name() -> Cow<'static, str> {
+        VecDeque::<T>::type_name()
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        value.to_list()?.iter().map(T::from_value).collect()
+    }
+}
+
+impl<T: IntoValue> IntoValue for Vec<T> {
+    fn into_value(self, under: &Map) -> Value {
+        VecDeque::from(self).into_value(under)
+    }
+}
+
+macro_rules! impl_map {
+    ($($map: ident),*) => {$(
+        impl<T: FromValue + 'static> FromValue for $map<String, T> {
+            fn type_name() -> Cow<'static, str> {
+                Cow::Owned(format!("map of {}", T::type_name()))
+            }
+
+            fn from_value(value: &Value) -> Option<Self> {
+                value.as_res()?.visit(|map: &Map| {
+                    map.iter()
+                        .map(|(k, v)| Some((k.clone(), T::from_value(v)?)))
+                        .collect()
+                })?
+            }
+        }
+
+        impl<T: IntoValue> IntoValue for $map<String, T> {
+            fn into_value(self, under: &Map) -> Value {
+                let mut map = Map::new_under(under);
+                for (k, v) in self {
+                    map.forced_set(k, v.into_value(under));
+                }
+                Value::Res(Resource::new(map))
+            }
+        }
+    )*};
+}
+
+impl_map!(HashMap, IndexMap);
+
+macro_rules! impl_tuple {
+    ($len: literal, $($name: ident),*) => {
+        impl<$($name: FromValue),*> FromValue for ($($name,)*) {
+            fn type_name() -> Cow<'static, str> {
+                Cow::Owned(format!("({})", [$($name::type_name()),*].join(", ")))
+            }
+
+            fn from_value(value: &Value) -> Option<Self> {
+                let mut list = value.to_list()?;
+                if list.len() != $len {
+                    return None;
+                }
+                Some(($($name::from_value(&list.pop_front()?)?,)*))
+            }
+        }
+
+        impl<$($name: IntoValue),*> IntoValue for ($($name,)*) {
+            #[allow(non_snake_case)]
+            fn into_value(self, under: &Map) -> Value {
+                let ($($name,)*) = self;
+                let list: VecDeque<Value> = VecDeque::from([$($name.into_value(under)),*]);
+                Value::Res(Resource::new(list))
+            }
+        }
+
+        impl<Func, Ret, $($name),*> NativeFn<($($name,)*)> for Func
+        where
+            Func: FnMut($($name),*) -> Result<Ret>,
+            Ret: IntoValue,
+            $($name: FromValue),*
+        {
+            fn shape() -> String {
+                <($($name,)*)>::type_name().into_owned()
+            }
+
+            fn call_native(&mut self, map: &Map, arg: Value) -> Result<Value> {
+                let line = map.line();
+                let mut list = arg.to_list().filter(|list| list.len() == $len).ok_or_else(|| {
+                    Error::new(
+                        format!("Unable to match with argument shape {}", Self::shape()),
+                        line,
+                    )
+                })?;
+                let mut index = 0;
+                let result = self($({
+                    index += 1;
+                    let value = list.pop_front().unwrap();
+                    $name::from_value(&value).ok_or_else(|| {
+                        Error::new(
+                            format!(
+                                "Expected {} as argument {} of shape {}, found {}",
+                                $name::type_name(),
+                                index,
+                                Self::shape(),
+                                value
+                            ),
+                            line,
+                        )
+                    })?
+                }),*);
+                result
+                    .map(|value| value.into_value(map))
+                    .map_err(|err| err.or_line(line))
+            }
+        }
+    };
+}
+
+impl_tuple!(2, A, B);
+impl_tuple!(3, A, B, C);
+impl_tuple!(4, A, B, C, D);
+impl_tuple!(5, A, B, C, D, E);
+impl_tuple!(6, A, B, C, D, E, F);
+
+impl<Func, Ret> NativeFn<()> for Func
+where
+    Func: FnMut() -> Result<Ret>,
+    Ret: IntoValue,
+{
+    fn shape() -> String {
+        "()".to_string()
+    }
+
+    fn call_native(&mut self, map: &Map, _arg: Value) -> Result<Value> {
+        self()
+            .map(|value| value.into_value(map))
+            .map_err(|err| err.or_line(map.line()))
+    }
+}
+
+impl<Func, Ret, A> NativeFn<(A,)> for Func
+where
+    Func: FnMut(A) -> Result<Ret>,
+    Ret: IntoValue,
+    A: FromValue,
+{
+    fn shape() -> String {
+        A::type_name().into_owned()
+    }
+
+    fn call_native(&mut self, map: &Map, arg: Value) -> Result<Value> {
+        let arg = arg.extract(map.line())?;
+        self(arg)
+            .map(|value| value.into_value(map))
+            .map_err(|err| err.or_line(map.line()))
+    }
+}
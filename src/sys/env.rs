@@ -1,11 +1,37 @@
 use super::*;
 
+/// An output stream that scripts write to, such as the standard output
+pub struct Sink(RefCell<Box<dyn Write>>);
+
 #[derive(Debug)]
 pub struct Env {
     pub modules: Vec<PathBuf>,
     pub linking: Vec<PathBuf>,
+    stdout: Sink,
+    stderr: Sink,
     base_path: RefCell<Vec<PathBuf>>,
     imported: RefCell<HashMap<PathBuf, WeakResource>>,
+    classes: RefCell<HashMap<std::any::TypeId, Rc<Class>>>,
+    /// Resource types serialized as strings, with how to write them
+    str_types: RefCell<HashMap<std::any::TypeId, WriteStr>>,
+}
+
+type WriteStr = fn(&Resource) -> Option<String>;
+
+impl Sink {
+    pub fn new(w: impl Write + 'static) -> Self {
+        Self(RefCell::new(Box::new(w)))
+    }
+
+    pub fn borrow_mut(&self) -> RefMut<'_, Box<dyn Write>> {
+        self.0.borrow_mut()
+    }
+}
+
+impl fmt::Debug for Sink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<sink>")
+    }
 }
 
 impl Env {
@@ -15,29 +41,51 @@ impl Env {
             .collect::<Vec<_>>()
     }
 
+    /// Create an environment searching only the current directory, ignoring environment variables
+    pub fn new() -> Self {
+        Self {
+            modules: vec![Path::new(".").to_path_buf()],
+            linking: vec![Path::new(".").to_path_buf()],
+            stdout: Sink::new(std::io::stdout()),
+            stderr: Sink::new(std::io::stderr()),
+            base_path: RefCell::new(vec![Path::new(".").to_path_buf()]),
+            imported: RefCell::new(HashMap::new()),
+            classes: RefCell::new(HashMap::new()),
+            str_types: RefCell::new(HashMap::new()),
+        }
+    }
+
     pub fn read() -> Self {
-        let mut modules: Vec<PathBuf> = Vec::new();
-        let mut linking: Vec<PathBuf> = Vec::new();
-        modules.push(Path::new(".").to_path_buf());
-        linking.push(Path::new(".").to_path_buf());
+        let mut env = Self::new();
 
-        modules.extend(
+        env.modules.extend(
             std::env::var("LEAS_PATH")
                 .map(Self::split_var)
                 .unwrap_or_default(),
         );
-        linking.extend(
+        env.linking.extend(
             std::env::var("LD_LIBRARY_PATH")
                 .map(Self::split_var)
                 .unwrap_or_default(),
         );
 
-        Self {
-            modules,
-            linking,
-            base_path: RefCell::new(vec![Path::new(".").to_path_buf()]),
-            imported: RefCell::new(HashMap::new()),
-        }
+        env
+    }
+
+    pub fn set_stdout(&mut self, w: impl Write + 'static) {
+        self.stdout = Sink::new(w);
+    }
+
+    pub fn set_stderr(&mut self, w: impl Write + 'static) {
+        self.stderr = Sink::new(w);
+    }
+
+    pub fn stdout(&self) -> RefMut<'_, Box<dyn Write>> {
+        self.stdout.borrow_mut()
+    }
+
+    pub fn stderr(&self) -> RefMut<'_, Box<dyn Write>> {
+        self.stderr.borrow_mut()
     }
 
     pub fn find_module(&self, name: &str) -> Option<PathBuf> {
@@ -49,7 +97,7 @@ impl Env {
                 module.to_path_buf()
             };
 
-            let path = module.join(&name);
+            let path = module.join(name);
             if path.exists() {
                 return Some(Self::locate_module(path));
             }
@@ -99,8 +147,34 @@ impl Env {
         self.imported.borrow_mut().insert(name, res.downgrade());
     }
 
+    pub fn register_class(&self, class: Class) {
+        self.classes
+            .borrow_mut()
+            .insert(class.type_id(), Rc::new(class));
+    }
+
+    /// Find the registered class of the value held by the resource
+    pub fn class_of(&self, res: &Resource) -> Option<Rc<Class>> {
+        self.classes.borrow().get(&res.type_id()).cloned()
+    }
+
+    /// Serialize resources holding `T` as strings, made by its `Display` implementation
+    pub fn serialize_as_str<T: fmt::Display + 'static>(&self) {
+        self.str_types
+            .borrow_mut()
+            .insert(std::any::TypeId::of::<RefCell<T>>(), |res| {
+                res.visit(|value: &T| value.to_string())
+            });
+    }
+
+    /// The string a resource is serialized as, if its type is registered with `serialize_as_str`
+    pub fn write_as_str(&self, res: &Resource) -> Option<String> {
+        let write = self.str_types.borrow().get(&res.type_id()).copied()?;
+        write(res)
+    }
+
     fn add_suffix(path: PathBuf) -> PathBuf {
-        if path.extension().map_or(false, |ext| ext == "lea") {
+        if path.extension().is_some_and(|ext| ext == "lea") {
             path
         } else {
             path.with_extension("lea")
@@ -115,3 +189,9 @@ impl Env {
         }
     }
 }
+
+impl Default for Env {
+    fn default() -> Self {
+        Self::new()
+    }
+}
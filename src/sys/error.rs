@@ -4,8 +4,14 @@ pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Debug)]
 pub enum ErrorData {
+    /// No position, for errors outside of any source like configuring an interpreter
+    Plain,
     Line(usize),
+    /// Line and column, for errors pointing at a character of the source
+    Position(usize, usize),
     Source(Box<dyn std::error::Error>),
+    /// Independent errors reported together, like all the errors of a compiled file
+    Many(Vec<Error>),
 }
 
 #[derive(Debug)]
@@ -22,10 +28,71 @@ impl Error {
         }
     }
 
+    pub fn plain(msg: impl ToString) -> Self {
+        Self {
+            msg: msg.to_string(),
+            data: ErrorData::Plain,
+        }
+    }
+
+    /// Error at a character of the source, with the column counted in characters from 1
+    pub fn at(msg: impl ToString, line: usize, column: usize) -> Self {
+        Self {
+            msg: msg.to_string(),
+            data: ErrorData::Position(line, column),
+        }
+    }
+
+    /// Report several errors at once ordered by location, nested groups are flattened
+    pub fn many(errors: impl IntoIterator<Item = Error>) -> Self {
+        let mut errors: Vec<_> = errors
+            .into_iter()
+            .flat_map(|err| match err.data {
+                ErrorData::Many(errors) => errors,
+                _ => vec![err],
+            })
+            .collect();
+        if errors.len() == 1 {
+            return errors.pop().unwrap();
+        }
+        errors.sort_by_key(|err| err.location().unwrap_or((usize::MAX, 0)));
+        Self {
+            msg: format!("{} errors found", errors.len()),
+            data: ErrorData::Many(errors),
+        }
+    }
+
+    /// Line and column the error points at, looking into its source, with column 0 if unknown
+    pub fn location(&self) -> Option<(usize, usize)> {
+        match self.data {
+            ErrorData::Plain => None,
+            ErrorData::Line(line) => Some((line, 0)),
+            ErrorData::Position(line, column) => Some((line, column)),
+            ErrorData::Source(ref err) => err.downcast_ref::<Error>()?.location(),
+            ErrorData::Many(ref errors) => errors.first()?.location(),
+        }
+    }
+
+    /// Every error reported by this one, itself unless it was made by `many`
+    pub fn errors(&self) -> Vec<&Error> {
+        match self.data {
+            ErrorData::Many(ref errors) => errors.iter().collect(),
+            _ => vec![self],
+        }
+    }
+
     pub fn with(self, msg: impl ToString) -> Self {
         Self::with_source(self, msg)
     }
 
+    /// Fill in the line of an error created without one, like those of native functions
+    pub fn or_line(self, line: usize) -> Self {
+        match self.data {
+            ErrorData::Plain => Self::new(self.msg, line),
+            _ => self,
+        }
+    }
+
     pub fn with_source(err: impl std::error::Error + 'static, msg: impl ToString) -> Self {
         Self {
             msg: msg.to_string(),
@@ -37,8 +104,19 @@ impl Error {
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self.data {
+            ErrorData::Plain => write!(f, "{}", self.msg),
             ErrorData::Line(line) => write!(f, "(Line {}) {}", line, self.msg),
+            ErrorData::Position(line, column) => {
+                write!(f, "(Line {}, column {}) {}", line, column, self.msg)
+            }
             ErrorData::Source(ref err) => write!(f, "[ {} ]\n{}", self.msg, err),
+            ErrorData::Many(ref errors) => {
+                write!(f, "[ {} ]", self.msg)?;
+                for err in errors {
+                    write!(f, "\n{}", err)?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -2,33 +2,49 @@ use super::*;
 
 #[derive(Debug)]
 pub struct Map {
-    data: HashMap<String, Value>,
-    pushed: HashMap<String, Vec<Value>>,
+    /// Keeps insertion order, so iterating a map is stable between runs.
+    /// Removed entries are left as `None` until they make up half of the entries,
+    /// so that removing does not shift the entries after them every time
+    data: IndexMap<String, Option<Value>>,
+    removed: usize,
+    /// Values shadowed by `push`, or `None` if the name was not set, restored in place by `pop`
+    pushed: HashMap<String, Vec<Option<Value>>>,
     snapshot: Vec<(HashSet<String>, HashSet<String>)>,
     line: Rc<Cell<usize>>,
 
     env: Rc<Env>,
 
     parent: Option<Box<Map>>,
+
+    /// Documentation from the `##` comments before the map
+    doc: Option<String>,
 }
 
 impl Map {
     pub fn new() -> Self {
+        Self::with_env(Env::read())
+    }
+
+    pub fn with_env(env: Env) -> Self {
         Self {
-            data: HashMap::new(),
+            data: IndexMap::new(),
+            removed: 0,
             pushed: HashMap::new(),
             snapshot: Vec::new(),
             line: Rc::new(Cell::new(1)),
 
-            env: Rc::new(Env::read()),
+            env: Rc::new(env),
 
             parent: None,
+
+            doc: None,
         }
     }
 
     pub fn new_under(map: &Map) -> Self {
         Self {
-            data: HashMap::new(),
+            data: IndexMap::new(),
+            removed: 0,
             pushed: HashMap::new(),
             snapshot: Vec::new(),
             line: map.line.clone(),
@@ -36,17 +52,33 @@ impl Map {
             env: map.env.clone(),
 
             parent: None,
+
+            doc: None,
         }
     }
 
     pub fn get(&self, k: &str) -> Option<&Value> {
         self.data
             .get(k)
+            .and_then(Option::as_ref)
             .or_else(|| self.parent.as_ref().and_then(|p| p.get(k)))
     }
 
     pub fn forced_set(&mut self, k: String, v: Value) -> Option<Value> {
-        self.data.insert(k, v)
+        match self.data.get_full_mut(&k) {
+            Some((_, _, Some(value))) => Some(std::mem::replace(value, v)),
+            // Set again after being removed, so it goes to the end
+            Some((index, _, None)) => {
+                self.data.shift_remove_index(index);
+                self.removed -= 1;
+                self.data.insert(k, Some(v));
+                None
+            }
+            None => {
+                self.data.insert(k, Some(v));
+                None
+            }
+        }
     }
 
     pub fn set(&mut self, k: String, v: Value) {
@@ -66,7 +98,23 @@ impl Map {
     }
 
     pub fn forced_rem(&mut self, k: &str) -> Option<Value> {
-        self.data.remove(k)
+        let (index, _, value) = self.data.get_full_mut(k)?;
+        let value = value.take()?;
+        if index + 1 == self.data.len() {
+            self.data.pop();
+            // Removed entries left at the end can go right away
+            while self.data.last().is_some_and(|(_, value)| value.is_none()) {
+                self.data.pop();
+                self.removed -= 1;
+            }
+        } else {
+            self.removed += 1;
+            if self.removed * 2 > self.data.len() {
+                self.data.retain(|_, value| value.is_some());
+                self.removed = 0;
+            }
+        }
+        Some(value)
     }
 
     pub fn rem(&mut self, k: Cow<str>) -> Option<Value> {
@@ -109,18 +157,8 @@ impl Map {
     }
 
     pub fn push(&mut self, k: String, v: Value) {
-        let value = self.forced_rem(&k);
-        if let Some(value) = value {
-            match self.pushed.get_mut(&k) {
-                Some(pushed) => {
-                    pushed.push(value);
-                }
-                None => {
-                    self.pushed.insert(k.to_string(), vec![value]);
-                }
-            }
-        }
-        self.forced_set(k, v);
+        let value = self.forced_set(k.clone(), v);
+        self.pushed.entry(k).or_default().push(value);
     }
 
     pub fn push_name(&mut self, k: impl ToString, v: Value) {
@@ -128,14 +166,16 @@ impl Map {
     }
 
     pub fn pop(&mut self, k: Cow<str>) {
-        match self.pushed.get_mut(k.as_ref()) {
-            Some(pushed) => {
-                if let Some(value) = pushed.pop() {
-                    self.set(k.into(), value);
-                }
+        match self
+            .pushed
+            .get_mut(k.as_ref())
+            .and_then(|pushed| pushed.pop())
+        {
+            Some(Some(value)) => {
+                self.forced_set(k.into(), value);
             }
-            None => {
-                self.rem(k);
+            _ => {
+                self.forced_rem(k.as_ref());
             }
         }
     }
@@ -161,6 +201,19 @@ impl Map {
         );
     }
 
+    /// Register a plain Rust function, converting its arguments and result automatically
+    pub fn register_fn<Args, F>(&mut self, name: impl ToString, mut f: F)
+    where
+        F: NativeFn<Args> + 'static,
+    {
+        self.register(name, move |map, arg| f.call_native(map, arg));
+    }
+
+    /// Expose a Rust type to every map sharing this environment
+    pub fn register_class(&mut self, class: Class) {
+        self.env.register_class(class);
+    }
+
     pub fn register_init(
         &mut self,
         name: impl ToString + fmt::Display,
@@ -182,6 +235,14 @@ impl Map {
         self.line.set(line);
     }
 
+    pub fn doc(&self) -> Option<&str> {
+        self.doc.as_deref()
+    }
+
+    pub fn set_doc(&mut self, doc: String) {
+        self.doc = Some(doc);
+    }
+
     pub fn line(&self) -> usize {
         self.line.get()
     }
@@ -194,14 +255,11 @@ impl Map {
         self.parent = Some(Box::new(parent));
     }
 
-    /// Link to a mutable reference of a map, leaving the original map in an invalid state.
+    /// Link to a mutable reference of a map, leaving an empty placeholder map in its place.
     /// Remember to call `unlink_to` to get the original map back.
-    pub unsafe fn link_to(&mut self, map: &mut Map) {
-        self.link(std::mem::replace(
-            map,
-            #[allow(invalid_value)]
-            std::mem::MaybeUninit::uninit().assume_init(),
-        ))
+    pub fn link_to(&mut self, map: &mut Map) {
+        let placeholder = Map::new_under(map);
+        self.link(std::mem::replace(map, placeholder))
     }
 
     pub fn unlink(&mut self) -> Option<Map> {
@@ -209,10 +267,9 @@ impl Map {
     }
 
     /// Unlink the current map with its parent, moving the parent map to the given mutable reference.
-    /// This function forgets the given map, and is *ONLY* used with `link_to`
+    /// This function drops the placeholder map, and is *ONLY* used with `link_to`
     pub fn unlink_to(&mut self, map: &mut Map) {
-        let unused_map = std::mem::replace(map, self.unlink().unwrap());
-        std::mem::forget(unused_map);
+        *map = self.unlink().unwrap();
     }
 
     pub fn parent(&self) -> Option<&Map> {
@@ -224,7 +281,9 @@ impl Map {
     }
 
     pub fn iter(&self) -> impl Iterator<Item = (&String, &Value)> {
-        self.data.iter()
+        self.data
+            .iter()
+            .filter_map(|(k, v)| v.as_ref().map(|v| (k, v)))
     }
 }
 
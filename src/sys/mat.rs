@@ -162,6 +162,12 @@ impl fmt::Display for Matcher {
     }
 }
 
+impl Default for MatcherEntry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Default for Matcher {
     fn default() -> Self {
         Self::new()
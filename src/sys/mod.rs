@@ -1,17 +1,29 @@
+mod class;
+mod cmp;
 mod coll;
+mod conv;
 mod env;
 mod error;
 mod map;
 mod mat;
 mod pool;
+#[cfg(feature = "serde")]
+mod serial;
+mod show;
 mod value;
 
 pub(super) use crate::prelude::*;
 
-pub use coll::Func;
-pub use env::Env;
+pub use class::{Class, ClassBuilder};
+pub use coll::{Func, FuncBody};
+pub use conv::{FromValue, IntoValue, NativeFn};
+pub use env::{Env, Sink};
 pub use error::{Error, Result};
 pub use map::Map;
 pub use mat::{Matcher, MatcherEntry};
 pub use pool::{Res, Resource, WeakResource};
+#[cfg(feature = "serde")]
+pub use serial::{deserialize, serializable, serialize, ValueSeed};
+#[cfg(feature = "bigint")]
+pub use value::Big;
 pub use value::{Bool, Float, Int, Uint, Value};
@@ -1,10 +1,23 @@
 use super::*;
 
 #[derive(Debug, Clone)]
-pub struct Resource(Rc<dyn Res>);
+pub struct Resource(Rc<Slot<dyn Res>>);
 
 #[derive(Clone)]
-pub struct WeakResource(Weak<dyn Res>);
+pub struct WeakResource(Weak<Slot<dyn Res>>);
+
+/// A shared value, with room for something derived from it
+struct Slot<T: ?Sized> {
+    /// Like the character index of a string, dropped whenever the value is visited mutably
+    cache: RefCell<Option<Box<dyn Any>>>,
+    value: T,
+}
+
+impl<T: fmt::Debug + ?Sized> fmt::Debug for Slot<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
 
 pub trait Res
 where
@@ -27,13 +40,67 @@ impl Resource {
     where
         T: Res + Sized + 'static,
     {
-        Self(Rc::new(RefCell::new(value)))
+        Self(Rc::new(Slot {
+            cache: RefCell::new(None),
+            value: RefCell::new(value),
+        }))
+    }
+
+    /// The type id of the cell holding the value, as used by `Class`
+    pub(crate) fn type_id(&self) -> std::any::TypeId {
+        self.0.value.as_any().type_id()
+    }
+
+    /// Address of the shared value, identifying the resource
+    pub fn as_ptr(&self) -> *const () {
+        Rc::as_ptr(&self.0) as *const ()
     }
 
     pub fn downgrade(&self) -> WeakResource {
         WeakResource(Rc::downgrade(&self.0))
     }
 
+    /// The cell holding the value, for callers that must not panic when it is already borrowed.
+    /// The cache is dropped, as the value may be changed through the cell
+    pub(crate) fn cell<T: 'static>(&self) -> Option<&RefCell<T>> {
+        let cell = self.0.value.as_any().downcast_ref::<RefCell<T>>()?;
+        self.0.cache.replace(None);
+        Some(cell)
+    }
+
+    /// Borrow the value for reading, leaving the cache as it is
+    pub(crate) fn borrow<T: 'static>(&self) -> Option<std::cell::Ref<'_, T>> {
+        Some(self.0.value.as_any().downcast_ref::<RefCell<T>>()?.borrow())
+    }
+
+    /// Run `f` with the cached value derived from this resource, making it first if needed
+    #[cfg(feature = "str")]
+    pub(crate) fn with_cache<C, R>(&self, make: impl FnOnce() -> C, f: impl FnOnce(&C) -> R) -> R
+    where
+        C: 'static,
+    {
+        let mut cache = self.0.cache.borrow_mut();
+        if !cache.as_ref().is_some_and(|cached| cached.is::<C>()) {
+            *cache = Some(Box::new(make()));
+        }
+        f(cache
+            .as_ref()
+            .and_then(|cached| cached.downcast_ref())
+            .unwrap())
+    }
+
+    /// Take the cached value out, to keep it up to date while the resource is visited mutably
+    #[cfg(feature = "str")]
+    pub(crate) fn take_cache<C: 'static>(&self) -> Option<C> {
+        let cached = self.0.cache.take()?;
+        cached.downcast().ok().map(|cached| *cached)
+    }
+
+    #[cfg(feature = "str")]
+    pub(crate) fn set_cache<C: 'static>(&self, cached: C) {
+        self.0.cache.replace(Some(Box::new(cached)));
+    }
+
     pub fn visit<T, F, R>(&self, f: F) -> Option<R>
     where
         T: 'static,
@@ -42,7 +109,7 @@ impl Resource {
     {
         Some(f(&self
             .0
-            .as_ref()
+            .value
             .as_any()
             .downcast_ref::<RefCell<T>>()?
             .borrow()))
@@ -54,12 +121,9 @@ impl Resource {
         F: FnOnce(&mut T) -> R,
         R: 'static,
     {
-        Some(f(&mut self
-            .0
-            .as_ref()
-            .as_any()
-            .downcast_ref::<RefCell<T>>()?
-            .borrow_mut()))
+        let cell = self.0.value.as_any().downcast_ref::<RefCell<T>>()?;
+        self.0.cache.replace(None);
+        Some(f(&mut cell.borrow_mut()))
     }
 }
 
@@ -0,0 +1,697 @@
+//! Serde support for `Value`.
+//!
+//! Values map to the serde data model as follows:
+//! - `int`, `uint`, `float` and `bool` map to the numbers and booleans of the same width
+//! - string resources map to strings, vector resources to sequences and map resources to maps
+//! - `null` maps to a missing option, and so does `stop` except in maps, where its entries are skipped
+//! - resources of types registered with [`Env::serialize_as_str`] map to strings, like TOML datetimes
+//! - functions and other resources are rejected with an error
+//!
+//! In the other direction, integers are read as `int` unless they only fit in `uint`,
+//! and both missing options and units are read as `null`. This holds both when deserializing
+//! with a [`ValueSeed`] and when converting a Rust value with [`serialize`].
+//!
+//! Values are written with [`serializable`] and read with [`deserialize`], which take the
+//! environment to find the types written as strings. Maps read from any format are created
+//! under a given map, so that they share its environment.
+
+use super::*;
+use serde::de::{self, IntoDeserializer};
+use serde::ser::{self, SerializeMap, SerializeSeq};
+use std::marker::PhantomData;
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::plain(msg)
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::plain(msg)
+    }
+}
+
+/// Contents of a resource that can be serialized, copied out so that no borrow is held
+enum Contents {
+    Str(String),
+    List(VecDeque<Value>),
+    Map(Vec<(String, Value)>),
+}
+
+impl Contents {
+    fn of(res: &Resource, env: &Env) -> std::result::Result<Self, String> {
+        if let Some(s) = res.visit(|s: &String| s.clone()) {
+            return Ok(Self::Str(s));
+        }
+        if let Some(list) = res.visit(|list: &VecDeque<Value>| list.clone()) {
+            return Ok(Self::List(list));
+        }
+        if let Some(map) = res.visit(|map: &Map| {
+            map.iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect::<Vec<_>>()
+        }) {
+            return Ok(Self::Map(map));
+        }
+        if let Some(s) = env.write_as_str(res) {
+            return Ok(Self::Str(s));
+        }
+        if res.visit_func(|_| ()).is_some() {
+            return Err("Functions cannot be serialized".to_string());
+        }
+        Err(format!("Resource {:?} cannot be serialized", res))
+    }
+}
+
+/// Serializes a value, keeping track of the resources being visited to reject cycles
+struct Guarded<'a> {
+    value: &'a Value,
+    env: &'a Env,
+    visiting: &'a RefCell<Vec<*const ()>>,
+}
+
+impl<'a> Guarded<'a> {
+    fn child(&self, value: &'a Value) -> Self {
+        Self {
+            value,
+            env: self.env,
+            visiting: self.visiting,
+        }
+    }
+}
+
+impl ser::Serialize for Guarded<'_> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let res = match self.value {
+            Value::Int(value) => return serializer.serialize_i64(*value),
+            Value::Uint(value) => return serializer.serialize_u64(*value),
+            Value::Float(value) => return serializer.serialize_f64(*value),
+            Value::Bool(value) => return serializer.serialize_bool(*value),
+            Value::Null | Value::Stop => return serializer.serialize_none(),
+            Value::Res(res) => res.clone(),
+            Value::Weak(weak) => weak
+                .upgrade()
+                .ok_or_else(|| ser::Error::custom("Cannot serialize deleted value"))?,
+        };
+
+        let ptr = res.as_ptr();
+        if self.visiting.borrow().contains(&ptr) {
+            return Err(ser::Error::custom(
+                "Cannot serialize value containing itself",
+            ));
+        }
+        let contents = Contents::of(&res, self.env).map_err(ser::Error::custom)?;
+
+        self.visiting.borrow_mut().push(ptr);
+        let result = match contents {
+            Contents::Str(s) => serializer.serialize_str(&s),
+            Contents::List(list) => (|| {
+                let mut seq = serializer.serialize_seq(Some(list.len()))?;
+                for value in list.iter() {
+                    seq.serialize_element(&self.child(value))?;
+                }
+                seq.end()
+            })(),
+            Contents::Map(entries) => (|| {
+                let mut map = serializer.serialize_map(None)?;
+                for (key, value) in entries.iter() {
+                    if *value != Value::Stop {
+                        map.serialize_entry(key, &self.child(value))?;
+                    }
+                }
+                map.end()
+            })(),
+        };
+        self.visiting.borrow_mut().pop();
+        result
+    }
+}
+
+/// Make the value serializable by any serde format, finding the types written as strings in `env`
+pub fn serializable<'a>(value: &'a Value, env: &'a Env) -> impl ser::Serialize + 'a {
+    /// Owns the set of visited resources that the guarded value borrows
+    struct Root<'a> {
+        value: &'a Value,
+        env: &'a Env,
+    }
+
+    impl ser::Serialize for Root<'_> {
+        fn serialize<S: ser::Serializer>(
+            &self,
+            serializer: S,
+        ) -> std::result::Result<S::Ok, S::Error> {
+            Guarded {
+                value: self.value,
+                env: self.env,
+                visiting: &RefCell::new(Vec::new()),
+            }
+            .serialize(serializer)
+        }
+    }
+
+    Root { value, env }
+}
+
+/// Deserializes values from any serde format, creating maps under the same environment as `under`
+#[derive(Clone, Copy)]
+pub struct ValueSeed<'a> {
+    under: &'a Map,
+}
+
+impl<'a> ValueSeed<'a> {
+    pub fn new(under: &'a Map) -> Self {
+        Self { under }
+    }
+}
+
+impl<'de> de::DeserializeSeed<'de> for ValueSeed<'_> {
+    type Value = Value;
+
+    fn deserialize<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> de::Visitor<'de> for ValueSeed<'_> {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a value representable in leas")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> std::result::Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> std::result::Result<Value, E> {
+        Ok(Value::Int(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> std::result::Result<Value, E> {
+        Ok(v.try_into().map_or(Value::Uint(v), Value::Int))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> std::result::Result<Value, E> {
+        Ok(Value::Float(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> std::result::Result<Value, E> {
+        Ok(v.into_value(self.under))
+    }
+
+    fn visit_string<E>(self, v: String) -> std::result::Result<Value, E> {
+        Ok(v.into_value(self.under))
+    }
+
+    fn visit_none<E>(self) -> std::result::Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_unit<E>(self) -> std::result::Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_newtype_struct<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<Value, A::Error> {
+        let mut list = VecDeque::<Value>::new();
+        while let Some(value) = seq.next_element_seed(self)? {
+            list.push_back(value);
+        }
+        Ok(Value::Res(Resource::new(list)))
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(
+        self,
+        mut access: A,
+    ) -> std::result::Result<Value, A::Error> {
+        let mut map = Map::new_under(self.under);
+        while let Some((key, value)) = access.next_entry_seed(PhantomData::<String>, self)? {
+            map.forced_set(key, value);
+        }
+        Ok(Value::Res(Resource::new(map)))
+    }
+}
+
+/// Convert any serializable Rust value to a leas value, creating maps under `under`
+pub fn serialize<T: ser::Serialize + ?Sized>(value: &T, under: &Map) -> Result<Value> {
+    value.serialize(ValueSerializer { under })
+}
+
+/// Convert a leas value to any deserializable Rust value, finding the types read as strings in `env`
+pub fn deserialize<T: de::DeserializeOwned>(value: &Value, env: &Env) -> Result<T> {
+    T::deserialize(ValueDeserializer {
+        value: value.clone(),
+        env,
+    })
+}
+
+/// Deserializes from a value, finding the types read as strings in `env`
+struct ValueDeserializer<'a> {
+    value: Value,
+    env: &'a Env,
+}
+
+/// Serializes to values, creating maps under the same environment as `under`
+#[derive(Clone, Copy)]
+struct ValueSerializer<'a> {
+    under: &'a Map,
+}
+
+struct SeqSerializer<'a> {
+    under: &'a Map,
+    list: VecDeque<Value>,
+    variant: Option<&'static str>,
+}
+
+struct MapSerializer<'a> {
+    under: &'a Map,
+    map: Map,
+    key: Option<String>,
+    variant: Option<&'static str>,
+}
+
+/// Wrap the value in a single-entry map, as serde does with externally tagged enums
+fn tagged(under: &Map, variant: Option<&'static str>, value: Value) -> Value {
+    match variant {
+        Some(variant) => {
+            let mut map = Map::new_under(under);
+            map.forced_set(variant.to_string(), value);
+            Value::Res(Resource::new(map))
+        }
+        None => value,
+    }
+}
+
+impl<'a> ser::Serializer for ValueSerializer<'a> {
+    type Ok = Value;
+    type Error = Error;
+
+    type SerializeSeq = SeqSerializer<'a>;
+    type SerializeTuple = SeqSerializer<'a>;
+    type SerializeTupleStruct = SeqSerializer<'a>;
+    type SerializeTupleVariant = SeqSerializer<'a>;
+    type SerializeMap = MapSerializer<'a>;
+    type SerializeStruct = MapSerializer<'a>;
+    type SerializeStructVariant = MapSerializer<'a>;
+
+    fn serialize_bool(self, v: bool) -> Result<Value> {
+        Ok(Value::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value> {
+        Ok(v.into_value(self.under))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value> {
+        Ok(v.into_value(self.under))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value> {
+        Ok(v.into_value(self.under))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value> {
+        Ok(v.into_value(self.under))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value> {
+        Ok(v.into_value(self.under))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value> {
+        Ok(v.into_value(self.under))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value> {
+        Ok(v.into_value(self.under))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value> {
+        Ok(v.into_value(self.under))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value> {
+        Ok(v.into_value(self.under))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value> {
+        Ok(v.into_value(self.under))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value> {
+        Ok(v.to_string().into_value(self.under))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value> {
+        Ok(v.into_value(self.under))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value> {
+        Ok(v.to_vec().into_value(self.under))
+    }
+
+    fn serialize_none(self) -> Result<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_some<T: ser::Serialize + ?Sized>(self, value: &T) -> Result<Value> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Value> {
+        Ok(variant.into_value(self.under))
+    }
+
+    fn serialize_newtype_struct<T: ser::Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ser::Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value> {
+        Ok(tagged(self.under, Some(variant), value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqSerializer<'a>> {
+        Ok(SeqSerializer {
+            under: self.under,
+            list: VecDeque::with_capacity(len.unwrap_or_default()),
+            variant: None,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqSerializer<'a>> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SeqSerializer<'a>> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer<'a>> {
+        Ok(SeqSerializer {
+            under: self.under,
+            list: VecDeque::with_capacity(len),
+            variant: Some(variant),
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<MapSerializer<'a>> {
+        Ok(MapSerializer {
+            under: self.under,
+            map: Map::new_under(self.under),
+            key: None,
+            variant: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<MapSerializer<'a>> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<MapSerializer<'a>> {
+        Ok(MapSerializer {
+            under: self.under,
+            map: Map::new_under(self.under),
+            key: None,
+            variant: Some(variant),
+        })
+    }
+}
+
+impl ser::SerializeSeq for SeqSerializer<'_> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: ser::Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.list
+            .push_back(value.serialize(ValueSerializer { under: self.under })?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        Ok(tagged(
+            self.under,
+            self.variant,
+            self.list.into_value(self.under),
+        ))
+    }
+}
+
+impl ser::SerializeTuple for SeqSerializer<'_> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: ser::Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqSerializer<'_> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ser::Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleVariant for SeqSerializer<'_> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ser::Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeMap for MapSerializer<'_> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_key<T: ser::Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        let key = key.serialize(ValueSerializer { under: self.under })?;
+        let key = match key {
+            Value::Int(_) | Value::Uint(_) | Value::Float(_) | Value::Bool(_) => key.to_string(),
+            _ => String::from_value(&key)
+                .ok_or_else(|| Error::plain("Map keys must be strings or numbers"))?,
+        };
+        self.key = Some(key);
+        Ok(())
+    }
+
+    fn serialize_value<T: ser::Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| Error::plain("Map value is serialized before its key"))?;
+        self.map
+            .forced_set(key, value.serialize(ValueSerializer { under: self.under })?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        Ok(tagged(
+            self.under,
+            self.variant,
+            Value::Res(Resource::new(self.map)),
+        ))
+    }
+}
+
+impl ser::SerializeStruct for MapSerializer<'_> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ser::Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.map.forced_set(
+            key.to_string(),
+            value.serialize(ValueSerializer { under: self.under })?,
+        );
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+impl ser::SerializeStructVariant for MapSerializer<'_> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ser::Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        ser::SerializeStruct::serialize_field(self, key, value)
+    }
+
+    fn end(self) -> Result<Value> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for ValueDeserializer<'_> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'a> ValueDeserializer<'a> {
+    fn child(&self, value: Value) -> Self {
+        Self {
+            value,
+            env: self.env,
+        }
+    }
+
+    fn contents(&self) -> Option<Contents> {
+        Contents::of(&self.value.as_res()?, self.env).ok()
+    }
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'_> {
+    type Error = Error;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let res = match self.value.clone() {
+            Value::Int(value) => return visitor.visit_i64(value),
+            Value::Uint(value) => return visitor.visit_u64(value),
+            Value::Float(value) => return visitor.visit_f64(value),
+            Value::Bool(value) => return visitor.visit_bool(value),
+            Value::Null => return visitor.visit_unit(),
+            Value::Stop => return visitor.visit_none(),
+            Value::Res(res) => res,
+            Value::Weak(weak) => weak
+                .upgrade()
+                .ok_or_else(|| Error::plain("Cannot deserialize deleted value"))?,
+        };
+        match Contents::of(&res, self.env).map_err(Error::plain)? {
+            Contents::Str(s) => visitor.visit_string(s),
+            Contents::List(list) => visitor.visit_seq(de::value::SeqDeserializer::new(
+                list.into_iter().map(|value| self.child(value)),
+            )),
+            Contents::Map(entries) => visitor.visit_map(de::value::MapDeserializer::new(
+                entries
+                    .into_iter()
+                    .filter(|(_, value)| *value != Value::Stop)
+                    .map(|(key, value)| (key, self.child(value))),
+            )),
+        }
+    }
+
+    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            Value::Null | Value::Stop => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        if let Some(s) = String::from_value(&self.value) {
+            return visitor.visit_enum(s.into_deserializer());
+        }
+        match self.contents() {
+            Some(Contents::Map(entries)) if entries.len() == 1 => visitor.visit_enum(
+                de::value::MapAccessDeserializer::new(de::value::MapDeserializer::new(
+                    entries
+                        .into_iter()
+                        .map(|(key, value)| (key, self.child(value))),
+                )),
+            ),
+            _ => Err(Error::plain(format!(
+                "Expected a string or a map with a single entry as enum, found {}",
+                self.value
+            ))),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
@@ -0,0 +1,208 @@
+//! Conversion of values to strings
+//!
+//! The str form writes strings as they are, the repr form quotes them.
+//! Numbers are written as they always were, with uints marked like `5u`
+//! and integral floats like `3.0`.
+//! Inside vectors and maps values are always written in repr form,
+//! and a container met again while writing itself is written as `...`.
+//! Weak references are written as the value they point to, as they are what names evaluate to.
+//! Maps with a `meta` map holding `to_str` (or `repr`) and registered classes with a
+//! `to_str` method provide their own conversion, and classes with entries are written like
+//! `dict {"a": 1}`. These are only used when a scope is at hand.
+
+use super::*;
+
+struct Printer<'a> {
+    map: Option<&'a mut Map>,
+    visiting: Vec<*const ()>,
+}
+
+impl Printer<'_> {
+    fn write(&mut self, value: &Value, repr: bool, out: &mut String) -> Result<()> {
+        match value {
+            Value::Int(value) => out.push_str(&value.to_string()),
+            Value::Uint(value) => out.push_str(&format!("{}u", value)),
+            Value::Float(value) => {
+                out.push_str(&value.to_string());
+                // Keep integral floats apart from ints
+                if value.is_finite() && value.fract() == 0.0 {
+                    out.push_str(".0");
+                }
+            }
+            Value::Bool(value) => out.push_str(&value.to_string()),
+            Value::Null => out.push_str("null"),
+            Value::Stop => out.push_str("stop"),
+            Value::Res(res) => return self.write_res(res, repr, out),
+            Value::Weak(weak) => match weak.upgrade() {
+                Some(res) => return self.write_res(&res, repr, out),
+                None => out.push_str("<dead weak>"),
+            },
+        }
+        Ok(())
+    }
+
+    fn write_res(&mut self, res: &Resource, repr: bool, out: &mut String) -> Result<()> {
+        if let Some(str) = res.visit(|str: &String| str.clone()) {
+            if repr {
+                out.push_str(&format!("{:?}", str));
+            } else {
+                out.push_str(&str);
+            }
+            return Ok(());
+        }
+        if let Some(name) = res.visit_func(|func| format!("{:?}", func.as_ref())) {
+            out.push_str(&name);
+            return Ok(());
+        }
+
+        let ptr = res.as_ptr();
+        if self.visiting.contains(&ptr) {
+            out.push_str("...");
+            return Ok(());
+        }
+        self.visiting.push(ptr);
+        let result = self.write_container(res, repr, out);
+        self.visiting.pop();
+        result
+    }
+
+    fn write_container(&mut self, res: &Resource, repr: bool, out: &mut String) -> Result<()> {
+        if let Some(vec) = res.visit(|vec: &VecDeque<Value>| vec.clone()) {
+            out.push('[');
+            for (index, value) in vec.iter().enumerate() {
+                if index > 0 {
+                    out.push_str(", ");
+                }
+                self.write(value, true, out)?;
+            }
+            out.push(']');
+        } else if let Some(entries) = res.visit(|map: &Map| {
+            map.iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect::<Vec<_>>()
+        }) {
+            if let Some(str) = self.call_meta(res, repr)? {
+                out.push_str(&str);
+                return Ok(());
+            }
+            out.push('{');
+            for (index, (key, value)) in entries.iter().enumerate() {
+                if index > 0 {
+                    out.push_str(", ");
+                }
+                out.push_str(key);
+                out.push_str(": ");
+                self.write(value, true, out)?;
+            }
+            out.push('}');
+        } else if let Some(map) = self.map.as_deref_mut() {
+            let Some(class) = map.env().class_of(res) else {
+                out.push_str("<resource>");
+                return Ok(());
+            };
+            if class.has_method("to_str") {
+                let value = class.call(res, "to_str", map, Value::Null)?;
+                let str = string_of(&value, "to_str", map.line())?;
+                out.push_str(&str);
+            } else if let Some(entries) = class.entries(res) {
+                out.push_str(class.name());
+                out.push_str(" {");
+                for (index, (key, value)) in entries.iter().enumerate() {
+                    if index > 0 {
+                        out.push_str(", ");
+                    }
+                    self.write(key, true, out)?;
+                    out.push_str(": ");
+                    self.write(value, true, out)?;
+                }
+                out.push('}');
+            } else {
+                out.push_str(&format!("<{}>", class.name()));
+            }
+        } else {
+            out.push_str("<resource>");
+        }
+        Ok(())
+    }
+
+    /// Call `meta.repr` or `meta.to_str` of a map with `this` set to it
+    fn call_meta(&mut self, res: &Resource, repr: bool) -> Result<Option<String>> {
+        let Some(map) = self.map.as_deref_mut() else {
+            return Ok(None);
+        };
+        let names: &[&str] = if repr {
+            &["repr", "to_str"]
+        } else {
+            &["to_str"]
+        };
+        let found = res
+            .visit(|this: &Map| {
+                let meta = this.get("meta")?.as_res()?;
+                meta.visit(|meta: &Map| {
+                    names
+                        .iter()
+                        .find_map(|name| Some((*name, meta.get(name)?.clone())))
+                })?
+            })
+            .flatten();
+        let Some((name, f)) = found else {
+            return Ok(None);
+        };
+
+        map.push_name("this", Value::Res(res.clone()));
+        let result = f.call(map, Value::Null);
+        map.pop_name("this");
+        let value = result.ok_or_else(|| {
+            Error::new(
+                format!("Value meta.{} is not a function, found {}", name, f),
+                map.line(),
+            )
+        })??;
+        string_of(&value, name, map.line()).map(Some)
+    }
+}
+
+fn string_of(value: &Value, name: &str, line: usize) -> Result<String> {
+    value
+        .as_res()
+        .and_then(|res| res.visit(|str: &String| str.clone()))
+        .ok_or_else(|| {
+            Error::new(
+                format!(
+                    "Conversion {} should return a string, found {}",
+                    name, value
+                ),
+                line,
+            )
+        })
+}
+
+impl Value {
+    /// Human readable form of the value, using conversions provided by scripts
+    pub fn to_str(&self, map: &mut Map) -> Result<String> {
+        self.show(Some(map), false)
+    }
+
+    /// Unambiguous form of the value, using conversions provided by scripts
+    pub fn repr(&self, map: &mut Map) -> Result<String> {
+        self.show(Some(map), true)
+    }
+
+    fn show(&self, map: Option<&mut Map>, repr: bool) -> Result<String> {
+        let mut out = String::new();
+        Printer {
+            map,
+            visiting: Vec::new(),
+        }
+        .write(self, repr, &mut out)?;
+        Ok(out)
+    }
+}
+
+/// Writes the str form, or the repr form with `{:#}`, without conversions provided by scripts
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = self.show(None, f.alternate()).map_err(|_| fmt::Error)?;
+        f.write_str(&str)
+    }
+}
@@ -4,6 +4,8 @@ pub type Int = i64;
 pub type Float = f64;
 pub type Uint = u64;
 pub type Bool = bool;
+#[cfg(feature = "bigint")]
+pub type Big = num_bigint::BigInt;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Value {
@@ -18,6 +20,69 @@ pub enum Value {
 }
 
 impl Value {
+    /// Name of the type of the value as seen by scripts, `"weak"` for every weak reference
+    pub fn type_of(&self, env: &Env) -> Cow<'static, str> {
+        let name = match self {
+            Self::Int(_) => "int",
+            Self::Float(_) => "float",
+            Self::Uint(_) => "uint",
+            Self::Bool(_) => "bool",
+            Self::Null => "null",
+            Self::Stop => "stop",
+            Self::Weak(_) => "weak",
+            Self::Res(res) => return Self::res_type_of(res, env),
+        };
+        Cow::Borrowed(name)
+    }
+
+    /// Name of the type a weak reference points to, `None` if it is dead.
+    /// Other values report their own type
+    pub fn target_type_of(&self, env: &Env) -> Option<Cow<'static, str>> {
+        match self {
+            Self::Weak(weak) => weak.upgrade().map(|res| Self::res_type_of(&res, env)),
+            _ => Some(self.type_of(env)),
+        }
+    }
+
+    fn res_type_of(res: &Resource, env: &Env) -> Cow<'static, str> {
+        let name = if res.visit(|_: &String| ()).is_some() {
+            "str"
+        } else if res.visit(|_: &VecDeque<Value>| ()).is_some() {
+            "vec"
+        } else if res.visit(|_: &Map| ()).is_some() {
+            "map"
+        } else if res.visit_func(|_| ()).is_some() {
+            "fn"
+        } else {
+            return env
+                .class_of(res)
+                .map(|class| Cow::Owned(class.name().to_string()))
+                .unwrap_or(Cow::Borrowed("resource"));
+        };
+        Cow::Borrowed(name)
+    }
+
+    /// Documentation attached to a function or a map
+    pub fn doc(&self) -> Option<String> {
+        let res = self.as_res()?;
+        res.visit_func(|func| func.doc.clone())
+            .or_else(|| res.visit(|map: &Map| map.doc().map(str::to_string)))
+            .flatten()
+    }
+
+    /// Attach documentation to a function or a map, other values are left as they are
+    pub fn set_doc(&self, doc: &str) {
+        let Some(res) = self.as_res() else {
+            return;
+        };
+        if res
+            .visit_mut_func(|func| func.doc = Some(doc.to_string()))
+            .is_none()
+        {
+            res.visit_mut(|map: &mut Map| map.set_doc(doc.to_string()));
+        }
+    }
+
     pub fn as_int(&self) -> Option<Int> {
         match self {
             Self::Int(value) => Some(*value),
@@ -119,18 +184,3 @@ impl Value {
             .ok_or_else(err)
     }
 }
-
-impl fmt::Display for Value {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Self::Int(value) => write!(f, "{}", value),
-            Self::Float(value) => write!(f, "{}", value),
-            Self::Uint(value) => write!(f, "{}u", value),
-            Self::Bool(value) => write!(f, "{}", value),
-            Self::Null => write!(f, "null"),
-            Self::Stop => write!(f, "stop"),
-            Self::Res(res) => write!(f, "{:?}", res),
-            Self::Weak(weak) => write!(f, "{:?}", weak),
-        }
-    }
-}
@@ -0,0 +1,178 @@
+//! Rust types exposed to scripts through registered classes
+
+use leas::sys::{Class, Error, Resource, Value};
+use leas::Interpreter;
+use std::path::Path;
+
+#[derive(Debug)]
+struct Counter {
+    count: i64,
+    step: i64,
+}
+
+fn counter_class() -> Class {
+    Class::builder::<Counter>("counter")
+        .getter("count", |c: &Counter| c.count)
+        .getter("step", |c: &Counter| c.step)
+        .setter("step", |c: &mut Counter, step: i64| c.step = step)
+        .method("tick", |c: &mut Counter, _, _| {
+            c.count += c.step;
+            Ok(Value::Int(c.count))
+        })
+        .method("add", |c: &mut Counter, map, arg| {
+            c.count += arg.extract::<i64>(map.line())?;
+            Ok(Value::Int(c.count))
+        })
+        .method("each", |c: &mut Counter, map, arg| {
+            let line = map.line();
+            arg.call(map, Value::Int(c.count))
+                .unwrap_or_else(|| Err(Error::new("Expected fn", line)))
+        })
+        .entries(|c: &Counter| {
+            vec![(
+                Value::Res(Resource::new("count".to_string())),
+                Value::Int(c.count),
+            )]
+        })
+        .build()
+}
+
+fn interpreter() -> Interpreter {
+    let mut interpreter = Interpreter::builder()
+        .read_env(false)
+        .path(Path::new(env!("CARGO_MANIFEST_DIR")).join("scripts"))
+        .build()
+        .unwrap();
+    let map = interpreter.map_mut();
+    map.register_class(counter_class());
+    map.register_fn("counter", |start: i64| {
+        Ok(Resource::new(Counter {
+            count: start,
+            step: 1,
+        }))
+    });
+    interpreter
+}
+
+#[test]
+fn getters_and_setters() {
+    let mut interpreter = interpreter();
+    interpreter
+        .run_str(
+            r#"
+            c = counter 5
+            before = c.step
+            c.step = 3
+            after = c.step
+            count = c.count
+            "#,
+        )
+        .unwrap();
+    assert_eq!(interpreter.get_global::<i64>("before").unwrap(), 1);
+    assert_eq!(interpreter.get_global::<i64>("after").unwrap(), 3);
+    assert_eq!(interpreter.get_global::<i64>("count").unwrap(), 5);
+}
+
+#[test]
+fn setters_check_the_type() {
+    let mut interpreter = interpreter();
+    let err = interpreter
+        .run_str("c = counter 0\nc.step = \"big\"")
+        .unwrap_err();
+    assert!(err.to_string().contains("(Line 2)"), "{err}");
+    assert!(err.to_string().contains("Expected int"), "{err}");
+}
+
+#[test]
+fn unknown_properties_are_errors() {
+    let mut interpreter = interpreter();
+    let err = interpreter
+        .run_str("c = counter 0\nx = c.size")
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("Type counter has no property named \"size\""),
+        "{err}"
+    );
+    let err = interpreter
+        .run_str("c = counter 0\nc.count = 1")
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("Property \"count\" of type counter cannot be set"),
+        "{err}"
+    );
+}
+
+#[test]
+fn methods_and_bound_methods() {
+    let mut interpreter = interpreter();
+    interpreter
+        .run_str(
+            r#"
+            c = counter 0
+            c:tick()
+            c:add 10
+            add = c.add
+            added = add 5
+            count = c.count
+            "#,
+        )
+        .unwrap();
+    assert_eq!(interpreter.get_global::<i64>("added").unwrap(), 16);
+    assert_eq!(interpreter.get_global::<i64>("count").unwrap(), 16);
+}
+
+#[test]
+fn type_of_and_show() {
+    let mut interpreter = interpreter();
+    interpreter
+        .run_str(
+            r#"
+            import sys
+            import str
+            c = counter 2
+            kind = sys.target_type_of c
+            shown = str.repr c
+            "#,
+        )
+        .unwrap();
+    assert_eq!(interpreter.get_global::<String>("kind").unwrap(), "counter");
+    assert_eq!(
+        interpreter.get_global::<String>("shown").unwrap(),
+        "counter {\"count\": 2}"
+    );
+}
+
+#[test]
+fn using_an_instance_from_its_own_method_is_an_error() {
+    let mut interpreter = interpreter();
+    let err = interpreter
+        .run_str(
+            r#"
+            c = counter 0
+            f = fn { c:add arg }
+            c:each f
+            "#,
+        )
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("Instance of counter cannot be used while one of its methods is running"),
+        "{err}"
+    );
+
+    let err = interpreter
+        .run_str("c = counter 0\nf = fn { c.count }\nc:each f")
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("Instance of counter cannot be used while one of its methods is running"),
+        "{err}"
+    );
+
+    interpreter
+        .run_str("c = counter 4\nf = fn { arg }\nn = c:each f")
+        .unwrap();
+    assert_eq!(interpreter.get_global::<i64>("n").unwrap(), 4);
+}
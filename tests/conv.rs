@@ -0,0 +1,150 @@
+//! Converting Rust values to and from leas values, and calling plain Rust functions from scripts
+
+use leas::sys::{Env, Error, FromValue, IntoValue, Map, Resource, Value};
+use leas::Interpreter;
+use std::collections::HashMap;
+
+fn interpreter() -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    let map = interpreter.map_mut();
+    map.register_fn("times", |s: String, n: i64| Ok(s.repeat(n as usize)));
+    map.register_fn("negate", |n: i64| Ok(-n));
+    map.register_fn("answer", || Ok(42u64));
+    map.register_fn("fail", |_: i64, _: i64| -> Result<(), Error> {
+        Err(Error::plain("Failed on purpose"))
+    });
+    map.register_fn("count", |words: Vec<String>| {
+        let mut counts = HashMap::new();
+        for word in words {
+            *counts.entry(word).or_insert(0u64) += 1;
+        }
+        Ok(counts)
+    });
+    interpreter
+}
+
+fn env_of(value: &Value) -> Option<*const Env> {
+    value
+        .as_res()
+        .and_then(|res: Resource| res.visit(|map: &Map| map.env() as *const Env))
+}
+
+#[test]
+fn registered_functions_convert_arguments_and_results() {
+    let mut interpreter = interpreter();
+    interpreter
+        .run_str(
+            r#"
+            args = ("ab", 3)
+            repeated = times args
+            negated = negate 5
+            answered = answer ()
+            "#,
+        )
+        .unwrap();
+    assert_eq!(
+        interpreter.get_global::<String>("repeated").unwrap(),
+        "ababab"
+    );
+    assert_eq!(interpreter.get_global::<i64>("negated").unwrap(), -5);
+    assert_eq!(interpreter.get_global::<u64>("answered").unwrap(), 42);
+}
+
+#[test]
+fn tuple_arguments_are_checked() {
+    let mut interpreter = interpreter();
+    let err = interpreter.run_str("x = times 1").unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("Unable to match with argument shape (str, int)"),
+        "{err}"
+    );
+
+    let err = interpreter
+        .run_str("args = (1, 2)\nx = times args")
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("Expected str as argument 1 of shape (str, int), found 1"),
+        "{err}"
+    );
+}
+
+#[test]
+fn errors_of_registered_functions_get_the_calling_line() {
+    let mut interpreter = interpreter();
+    let err = interpreter
+        .run_str("\nargs = (1, 2)\nx = fail args")
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("(Line 3) Failed on purpose"),
+        "{err}"
+    );
+}
+
+#[test]
+fn maps_returned_by_functions_share_the_environment() {
+    let mut interpreter = interpreter();
+    interpreter
+        .run_str(
+            r#"
+            words = ("a", "b", "a")
+            counts = count words
+            "#,
+        )
+        .unwrap();
+    let counts = interpreter
+        .get_global::<HashMap<String, u64>>("counts")
+        .unwrap();
+    assert_eq!(
+        counts,
+        HashMap::from([("a".to_string(), 2), ("b".to_string(), 1)])
+    );
+
+    let value = interpreter.get_global::<Value>("counts").unwrap();
+    assert_eq!(env_of(&value), Some(interpreter.map().env() as *const Env));
+}
+
+#[test]
+fn into_value_creates_maps_under_the_given_map() {
+    let interpreter = Interpreter::new();
+    let value = vec![HashMap::from([("a".to_string(), 1i64)])].into_value(interpreter.map());
+    let inner = Vec::<Value>::from_value(&value).unwrap();
+    assert_eq!(
+        env_of(&inner[0]),
+        Some(interpreter.map().env() as *const Env)
+    );
+}
+
+#[test]
+fn values_round_trip() {
+    let interpreter = Interpreter::new();
+    let map = interpreter.map();
+
+    let value = (1i64, "two", Some(3.5f64)).into_value(map);
+    assert_eq!(
+        <(i64, String, Option<f64>)>::from_value(&value),
+        Some((1, "two".to_string(), Some(3.5)))
+    );
+
+    let value = vec![Some(1u64), None].into_value(map);
+    assert_eq!(
+        Vec::<Option<u64>>::from_value(&value),
+        Some(vec![Some(1), None])
+    );
+
+    assert!(matches!(None::<i64>.into_value(map), Value::Stop));
+    assert!(matches!(().into_value(map), Value::Null));
+    assert_eq!(char::from_value(&'x'.into_value(map)), Some('x'));
+}
+
+#[test]
+fn mismatched_values_are_rejected() {
+    let interpreter = Interpreter::new();
+    let map = interpreter.map();
+
+    assert_eq!(i64::from_value(&"1".into_value(map)), None);
+    assert_eq!(<(i64, i64)>::from_value(&vec![1i64].into_value(map)), None);
+    assert_eq!(Vec::<String>::from_value(&vec![1i64].into_value(map)), None);
+    assert_eq!(<i64 as FromValue>::type_name(), "int");
+}
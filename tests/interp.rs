@@ -0,0 +1,170 @@
+//! Embedding the interpreter: running sources, globals, calls and builder options
+
+use leas::sys::Value;
+use leas::Interpreter;
+use std::cell::RefCell;
+use std::io::Write;
+use std::path::Path;
+use std::rc::Rc;
+
+/// Output shared with the interpreter, to read what scripts printed
+#[derive(Clone, Default)]
+struct Shared(Rc<RefCell<Vec<u8>>>);
+
+impl Shared {
+    fn text(&self) -> String {
+        String::from_utf8(self.0.borrow().clone()).unwrap()
+    }
+}
+
+impl Write for Shared {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn scripts() -> std::path::PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("scripts")
+}
+
+#[test]
+fn run_str_returns_the_last_value() {
+    let mut interpreter = Interpreter::new();
+    let value = interpreter.run_str("a = 1\nb = 2\nb").unwrap();
+    assert!(matches!(value, Value::Int(2)), "{value:?}");
+
+    let err = interpreter.run_str("c = 1 =").unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("Missing operand for binary operator Asn"),
+        "{err}"
+    );
+    // Globals set before the error are kept
+    assert_eq!(interpreter.get_global::<i64>("a").unwrap(), 1);
+}
+
+#[test]
+fn globals_are_converted() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_global("name", "leas");
+    interpreter.set_global("pair", (1i64, 2.5f64));
+    interpreter.run_str("copy = name\nsecond = pair").unwrap();
+
+    assert_eq!(interpreter.get_global::<String>("copy").unwrap(), "leas");
+    assert_eq!(
+        interpreter.get_global::<(i64, f64)>("second").unwrap(),
+        (1, 2.5)
+    );
+
+    let err = interpreter.get_global::<i64>("missing").unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("Required value missing is not found"),
+        "{err}"
+    );
+    let err = interpreter.get_global::<i64>("name").unwrap_err();
+    assert!(err.to_string().contains("Expected int"), "{err}");
+}
+
+#[test]
+fn call_passes_arguments_and_returns_owned_values() {
+    let mut interpreter = Interpreter::builder()
+        .read_env(false)
+        .path(scripts())
+        .build()
+        .unwrap();
+    interpreter
+        .run_str(
+            r#"
+            import int
+            id = fn { arg }
+            sum = fn { int.add arg }
+            make = fn {
+                made = "local"
+                move made
+            }
+            "#,
+        )
+        .unwrap();
+
+    assert_eq!(interpreter.call::<String>("id", "hello").unwrap(), "hello");
+    assert_eq!(
+        interpreter.call::<Vec<i64>>("id", vec![1i64, 2]).unwrap(),
+        [1, 2]
+    );
+    assert_eq!(interpreter.call::<i64>("sum", (2i64, 3i64)).unwrap(), 5);
+    assert_eq!(interpreter.call::<String>("make", ()).unwrap(), "local");
+
+    let err = interpreter.call::<i64>("id", "hello").unwrap_err();
+    assert!(err.to_string().contains("Expected int"), "{err}");
+    interpreter.set_global("number", 1i64);
+    let err = interpreter.call::<i64>("number", ()).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("Cannot call value 1 named \"number\""),
+        "{err}"
+    );
+}
+
+#[test]
+fn builder_sets_the_output_sinks() {
+    let (stdout, stderr) = (Shared::default(), Shared::default());
+    let mut interpreter = Interpreter::builder()
+        .read_env(false)
+        .path(scripts())
+        .stdout(stdout.clone())
+        .stderr(stderr.clone())
+        .build()
+        .unwrap();
+    interpreter
+        .run_str("import str\nstr.println \"out\"\nstr.eprintln \"err\"")
+        .unwrap();
+    assert_eq!(stdout.text(), "out\n");
+    assert_eq!(stderr.text(), "err\n");
+}
+
+#[test]
+fn builder_enables_only_the_given_modules() {
+    let mut interpreter = Interpreter::builder()
+        .read_env(false)
+        .no_modules()
+        .module("int")
+        .build()
+        .unwrap();
+    interpreter.run_str("x = 1").unwrap();
+    assert!(interpreter.run_str("import str").is_err());
+
+    let err = Interpreter::builder()
+        .read_env(false)
+        .modules(["int", "nope"])
+        .build()
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("Module \"nope\" is not available"),
+        "{err}"
+    );
+}
+
+#[test]
+fn builder_adds_module_paths() {
+    let dir = std::env::temp_dir().join(format!("leas-interp-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("greeting.lea"), "word = \"hi\"\n").unwrap();
+
+    let mut interpreter = Interpreter::builder()
+        .read_env(false)
+        .path(&dir)
+        .build()
+        .unwrap();
+    interpreter
+        .run_str("import greeting\nword = greeting.word")
+        .unwrap();
+    assert_eq!(interpreter.get_global::<String>("word").unwrap(), "hi");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
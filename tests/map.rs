@@ -0,0 +1,73 @@
+//! Entries of maps, which keep their insertion order
+
+use leas::sys::{Map, Value};
+
+fn keys(map: &Map) -> Vec<&str> {
+    map.iter().map(|(k, _)| k.as_str()).collect()
+}
+
+fn map_of(keys: &[&str]) -> Map {
+    let mut map = Map::new();
+    for (index, k) in keys.iter().enumerate() {
+        map.forced_set(k.to_string(), Value::Int(index as i64));
+    }
+    map
+}
+
+#[test]
+fn iteration_follows_insertion_order() {
+    let mut map = map_of(&["zeta", "alpha", "mid", "beta"]);
+    assert_eq!(keys(&map), ["zeta", "alpha", "mid", "beta"]);
+
+    // Setting an existing key keeps its place
+    map.forced_set("alpha".to_string(), Value::Int(10));
+    assert_eq!(keys(&map), ["zeta", "alpha", "mid", "beta"]);
+
+    // Removing keeps the order of the others, and setting again goes to the end
+    assert!(matches!(map.forced_rem("alpha"), Some(Value::Int(10))));
+    assert!(map.forced_rem("alpha").is_none());
+    assert!(map.get("alpha").is_none());
+    assert_eq!(keys(&map), ["zeta", "mid", "beta"]);
+    map.forced_set("gamma".to_string(), Value::Int(4));
+    map.forced_set("alpha".to_string(), Value::Int(5));
+    assert_eq!(keys(&map), ["zeta", "mid", "beta", "gamma", "alpha"]);
+}
+
+#[test]
+fn removing_many_entries_keeps_the_rest() {
+    let names = (0..1000).map(|i| format!("k{i}")).collect::<Vec<_>>();
+    let mut map = map_of(&names.iter().map(String::as_str).collect::<Vec<_>>());
+    for name in names.iter().step_by(3) {
+        assert!(map.forced_rem(name).is_some());
+    }
+    let expected = names
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| i % 3 != 0)
+        .map(|(_, name)| name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(keys(&map), expected);
+    for (i, name) in names.iter().enumerate() {
+        assert_eq!(map.get(name).is_some(), i % 3 != 0, "{name}");
+    }
+
+    // Removing from the end, like names pushed and popped, leaves nothing behind
+    for name in expected.iter().rev() {
+        assert!(map.forced_rem(name).is_some());
+    }
+    assert_eq!(keys(&map), Vec::<&str>::new());
+    map.forced_set("k0".to_string(), Value::Null);
+    assert_eq!(keys(&map), ["k0"]);
+}
+
+#[test]
+fn pushed_names_are_restored_in_place() {
+    let mut map = map_of(&["a", "b"]);
+    map.push_name("a", Value::Int(7));
+    map.push_name("this", Value::Int(8));
+    assert_eq!(keys(&map), ["a", "b", "this"]);
+    map.pop_name("this");
+    map.pop_name("a");
+    assert_eq!(keys(&map), ["a", "b"]);
+    assert!(matches!(map.get("a"), Some(Value::Int(0))));
+}
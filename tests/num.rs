@@ -0,0 +1,107 @@
+//! Conversions and arithmetic of numbers failing instead of wrapping or saturating
+
+#![cfg(all(feature = "int", feature = "float"))]
+
+use leas::Interpreter;
+use std::path::Path;
+
+fn interpreter() -> Interpreter {
+    let mut interpreter = Interpreter::builder()
+        .read_env(false)
+        .path(Path::new(env!("CARGO_MANIFEST_DIR")).join("scripts"))
+        .build()
+        .unwrap();
+    interpreter.run_str("import int\nimport float").unwrap();
+    interpreter
+}
+
+/// Run a single expression, returning its error message
+fn error_of(source: &str) -> String {
+    let mut interpreter = interpreter();
+    interpreter
+        .run_str(&format!("x = {source}"))
+        .unwrap_err()
+        .to_string()
+}
+
+#[test]
+fn floats_in_range_are_truncated() {
+    let mut interpreter = interpreter();
+    interpreter
+        .run_str(
+            r#"
+            a = float.to_int 2.9
+            b = float.to_int -2.9
+            c = float.to_uint -0.5
+            d = float.to_int -9223372036854775808.0
+            e = float.to_uint 18446744073709549568.0
+            "#,
+        )
+        .unwrap();
+    assert_eq!(interpreter.get_global::<i64>("a").unwrap(), 2);
+    assert_eq!(interpreter.get_global::<i64>("b").unwrap(), -2);
+    assert_eq!(interpreter.get_global::<u64>("c").unwrap(), 0);
+    assert_eq!(interpreter.get_global::<i64>("d").unwrap(), i64::MIN);
+    assert_eq!(
+        interpreter.get_global::<u64>("e").unwrap(),
+        18446744073709549568
+    );
+}
+
+#[test]
+fn floats_out_of_range_are_errors() {
+    for (source, expected) in [
+        (
+            "float.to_int 9223372036854775808.0",
+            "Float 9223372036854776000 is out of range of int",
+        ),
+        ("float.to_int float.nan", "Float NaN is out of range of int"),
+        ("float.to_int float.inf", "Float inf is out of range of int"),
+        ("float.to_uint -1.0", "Float -1 is out of range of uint"),
+        (
+            "float.to_uint 18446744073709551616.0",
+            "Float 18446744073709552000 is out of range of uint",
+        ),
+        (
+            "float.to_uint float.nan",
+            "Float NaN is out of range of uint",
+        ),
+    ] {
+        let err = error_of(source);
+        assert!(err.contains(expected), "{source}: {err}");
+    }
+}
+
+#[test]
+fn int_overflow_is_an_error() {
+    for (source, expected) in [
+        (
+            "int.pow(2, 63u)",
+            "Integer overflow when computing 2 to the power of 63",
+        ),
+        (
+            "int.pow(2, 4294967296u)",
+            "Integer overflow when computing 2 to the power of 4294967296",
+        ),
+        (
+            "int.gcd(-9223372036854775808, 0)",
+            "Integer overflow when computing gcd 9223372036854775808",
+        ),
+        ("int.shl(1, 64u)", "Cannot shift 1 left by 64 bits"),
+        (
+            "int.shr(-1, 4294967296u)",
+            "Cannot shift -1 right by 4294967296 bits",
+        ),
+    ] {
+        let err = error_of(source);
+        assert!(err.contains(expected), "{source}: {err}");
+    }
+
+    let mut interpreter = interpreter();
+    interpreter
+        .run_str("p = int.pow(-2, 63u)\ng = int.gcd(-9223372036854775808, 6)\ns = int.shl(1, 63u)")
+        .unwrap();
+    assert_eq!(interpreter.get_global::<i64>("p").unwrap(), i64::MIN);
+    assert_eq!(interpreter.get_global::<i64>("g").unwrap(), 2);
+    assert_eq!(interpreter.get_global::<i64>("s").unwrap(), i64::MIN);
+}
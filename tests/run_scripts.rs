@@ -0,0 +1,121 @@
+//! Runs every script in `tests/scripts` and compares what it prints, followed by the error
+//! it stops with if any, with the `.out` file of the same name.
+//!
+//! A script starting with a line like `# requires: regex, bigint` is skipped unless those
+//! features are enabled. Run with `LEAS_BLESS=1` to write the `.out` files from the results.
+
+use leas::Interpreter;
+use std::cell::RefCell;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+const FEATURES: &[(&str, bool)] = &[
+    ("bigint", cfg!(feature = "bigint")),
+    ("bool", cfg!(feature = "bool")),
+    ("char", cfg!(feature = "char")),
+    ("dict", cfg!(feature = "dict")),
+    ("float", cfg!(feature = "float")),
+    ("int", cfg!(feature = "int")),
+    ("iter", cfg!(feature = "iter")),
+    ("json", cfg!(feature = "json")),
+    ("regex", cfg!(feature = "regex")),
+    ("str", cfg!(feature = "str")),
+    ("sys", cfg!(feature = "sys")),
+    ("toml", cfg!(feature = "toml_c")),
+    ("uint", cfg!(feature = "uint")),
+    ("vec", cfg!(feature = "vec")),
+];
+
+/// Output shared with the interpreter, which takes ownership of its sink
+#[derive(Clone, Default)]
+struct Output(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Features a script requires that are not enabled
+fn missing_features(source: &str) -> Vec<&str> {
+    let Some(required) = source
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix("# requires:"))
+    else {
+        return Vec::new();
+    };
+    required
+        .split(',')
+        .map(str::trim)
+        .filter(|name| {
+            !FEATURES
+                .iter()
+                .any(|(feature, enabled)| feature == name && *enabled)
+        })
+        .collect()
+}
+
+fn run(path: &Path, source: &str) -> String {
+    let output = Output::default();
+    let mut interpreter = Interpreter::builder()
+        .read_env(false)
+        .path(Path::new(env!("CARGO_MANIFEST_DIR")).join("scripts"))
+        .stdout(output.clone())
+        .build()
+        .unwrap_or_else(|err| panic!("Cannot build interpreter for {:?}\n{}", path, err));
+    let result = interpreter.run_str(source);
+
+    let mut text = String::from_utf8_lossy(&output.0.borrow()).into_owned();
+    if let Err(err) = result {
+        if !text.is_empty() && !text.ends_with('\n') {
+            text.push('\n');
+        }
+        text.push_str(&format!("[error]\n{}\n", err));
+    }
+    text
+}
+
+#[test]
+fn scripts() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/scripts");
+    let bless = std::env::var_os("LEAS_BLESS").is_some();
+
+    let mut paths = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "lea"))
+        .collect::<Vec<PathBuf>>();
+    paths.sort();
+    assert!(!paths.is_empty(), "No scripts found in {:?}", dir);
+
+    let mut failed = Vec::new();
+    for path in paths {
+        let source = fs::read_to_string(&path).unwrap();
+        if !missing_features(&source).is_empty() {
+            continue;
+        }
+        let actual = run(&path, &source);
+        let expected_path = path.with_extension("out");
+        if bless {
+            fs::write(&expected_path, &actual).unwrap();
+            continue;
+        }
+        let expected = fs::read_to_string(&expected_path).unwrap_or_default();
+        if actual != expected {
+            failed.push(format!(
+                "{}\n--- expected ---\n{}--- actual ---\n{}",
+                path.display(),
+                expected,
+                actual
+            ));
+        }
+    }
+    assert!(failed.is_empty(), "\n{}", failed.join("\n"));
+}
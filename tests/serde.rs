@@ -0,0 +1,157 @@
+//! Converting between leas values and Rust values through serde
+
+#![cfg(feature = "serde")]
+
+use leas::sys::{self, Env, Map, Resource, Value};
+use leas::Interpreter;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Shape {
+    Point,
+    Circle { radius: f64 },
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Drawing {
+    name: String,
+    layer: i64,
+    visible: bool,
+    tags: Vec<String>,
+    shapes: Vec<Shape>,
+    parent: Option<String>,
+    extra: BTreeMap<String, u64>,
+}
+
+fn drawing() -> Drawing {
+    Drawing {
+        name: "sketch".to_string(),
+        layer: -2,
+        visible: true,
+        tags: vec!["a".to_string(), "b".to_string()],
+        shapes: vec![Shape::Point, Shape::Circle { radius: 1.5 }],
+        parent: None,
+        extra: BTreeMap::from([("max".to_string(), u64::MAX)]),
+    }
+}
+
+#[test]
+fn round_trip() {
+    let interpreter = Interpreter::new();
+    let value = sys::serialize(&drawing(), interpreter.map()).unwrap();
+    let back: Drawing = sys::deserialize(&value, interpreter.map().env()).unwrap();
+    assert_eq!(back, drawing());
+}
+
+#[test]
+fn maps_are_created_under_the_given_map() {
+    let interpreter = Interpreter::new();
+    let value = sys::serialize(&drawing(), interpreter.map()).unwrap();
+    let env = value
+        .as_res()
+        .and_then(|res: Resource| res.visit(|map: &Map| map.env() as *const Env));
+    assert_eq!(env, Some(interpreter.map().env() as *const Env));
+}
+
+#[test]
+fn none_and_unit_serialize_as_null() {
+    let interpreter = Interpreter::new();
+    let none = sys::serialize(&None::<i64>, interpreter.map()).unwrap();
+    assert!(matches!(none, Value::Null));
+    let unit = sys::serialize(&(), interpreter.map()).unwrap();
+    assert!(matches!(unit, Value::Null));
+}
+
+#[test]
+fn null_and_stop_deserialize_as_none() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .run_str(
+            r#"
+            a = null
+            b = stop
+            "#,
+        )
+        .unwrap();
+    let env = interpreter.map().env();
+    for name in ["a", "b"] {
+        let value = interpreter.map().get(name).unwrap();
+        let read: Option<i64> = sys::deserialize(value, env).unwrap();
+        assert_eq!(read, None, "{name}");
+    }
+}
+
+#[test]
+fn stop_entries_are_skipped() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .run_str(
+            r#"
+            m = map {
+                kept = 1
+                gone = stop
+                empty = null
+            }
+            "#,
+        )
+        .unwrap();
+    let value = interpreter.map().get("m").unwrap();
+    let read: BTreeMap<String, Option<i64>> =
+        sys::deserialize(value, interpreter.map().env()).unwrap();
+    assert_eq!(
+        read,
+        BTreeMap::from([("kept".to_string(), Some(1)), ("empty".to_string(), None)])
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn serializable_rejects_values_containing_themselves() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .run_str(
+            r#"
+            m = map {
+                a = 1
+            }
+            m.me = m
+            "#,
+        )
+        .unwrap();
+    let value = interpreter.map().get("m").unwrap();
+    let result = serde_json::to_string(&sys::serializable(value, interpreter.map().env()));
+    assert!(result.is_err());
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_unwrap_creates_maps_under_the_calling_map() {
+    let mut interpreter = Interpreter::builder()
+        .read_env(false)
+        .path(std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("scripts"))
+        .build()
+        .unwrap();
+    interpreter
+        .run_str(
+            r#"
+            import json
+            src = map {
+                a = map {
+                    b = 1
+                }
+            }
+            m = json.unwrap (json.from src)
+            inner = m.a
+            "#,
+        )
+        .unwrap();
+    let env = interpreter.map().env() as *const Env;
+    for name in ["m", "inner"] {
+        let value = interpreter.map().get(name).unwrap();
+        let inner = value
+            .as_res()
+            .and_then(|res: Resource| res.visit(|map: &Map| map.env() as *const Env));
+        assert_eq!(inner, Some(env), "{name}");
+    }
+}
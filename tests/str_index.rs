@@ -0,0 +1,32 @@
+//! Character indexes of strings changed from Rust
+
+#![cfg(feature = "str")]
+
+use leas::Interpreter;
+use std::path::Path;
+
+#[test]
+fn strings_changed_from_rust_are_indexed_again() {
+    let mut interpreter = Interpreter::builder()
+        .read_env(false)
+        .path(Path::new(env!("CARGO_MANIFEST_DIR")).join("scripts"))
+        .build()
+        .unwrap();
+    interpreter.set_global("s", "é".repeat(100));
+    interpreter
+        .run_str("import str\nbefore = str.get(s, 99u)")
+        .unwrap();
+    assert_eq!(interpreter.get_global::<char>("before").unwrap(), 'é');
+
+    // Same byte length, different character boundaries
+    let s = interpreter.map().get("s").unwrap().as_res().unwrap();
+    s.visit_mut(|s: &mut String| *s = "a".repeat(100) + &"é".repeat(50))
+        .unwrap();
+
+    interpreter
+        .run_str("after = str.get(s, 120u)\nlast = str.get(s, 149u)\nend = str.get(s, 150u)")
+        .unwrap();
+    assert_eq!(interpreter.get_global::<char>("after").unwrap(), 'é');
+    assert_eq!(interpreter.get_global::<char>("last").unwrap(), 'é');
+    assert_eq!(interpreter.get_global::<Option<char>>("end").unwrap(), None);
+}
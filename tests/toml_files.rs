@@ -0,0 +1,75 @@
+//! Saving and loading toml files, in a directory of their own under the temporary directory
+
+#![cfg(feature = "toml_c")]
+
+use leas::sys::{Env, Map, Resource};
+use leas::Interpreter;
+use std::fs;
+use std::path::Path;
+
+fn interpreter() -> Interpreter {
+    Interpreter::builder()
+        .read_env(false)
+        .path(Path::new(env!("CARGO_MANIFEST_DIR")).join("scripts"))
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn save_and_load() {
+    let dir = std::env::temp_dir().join(format!("leas-toml-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("saved.toml");
+
+    let mut interpreter = interpreter();
+    interpreter.set_global("path", path.to_string_lossy().into_owned());
+    interpreter
+        .run_str(
+            r#"
+            import toml
+            doc = toml.de """
+                title = "leas"
+                when = 1979-05-27T07:32:00-08:00
+
+                [owner]
+                name = "Tom"
+                """
+            toml.save(path, doc)
+            loaded = toml.load path
+            name = loaded.owner.name
+            day = loaded.when.day
+            "#,
+        )
+        .unwrap();
+    let saved = fs::read_to_string(&path).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(
+        saved.contains("when = 1979-05-27T07:32:00-08:00"),
+        "{}",
+        saved
+    );
+    assert_eq!(interpreter.get_global::<String>("name").unwrap(), "Tom");
+    assert_eq!(interpreter.get_global::<u64>("day").unwrap(), 27);
+
+    // Tables share the environment of the interpreter
+    let loaded = interpreter.get_global::<Resource>("loaded").unwrap();
+    let env = interpreter.map().env() as *const Env;
+    assert_eq!(loaded.visit(|map: &Map| map.env() as *const Env), Some(env));
+}
+
+#[test]
+fn load_missing_file() {
+    let path = std::env::temp_dir().join(format!("leas-missing-{}.toml", std::process::id()));
+
+    let mut interpreter = interpreter();
+    interpreter.set_global("path", path.to_string_lossy().into_owned());
+    let err = interpreter
+        .run_str("import toml\ntoml.load path")
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("When reading toml file"),
+        "{}",
+        err
+    );
+}